@@ -27,6 +27,13 @@ fn main() -> anyhow::Result<()> {
         get_pkg_version(&lockfile, "yellowstone-grpc-proto")
     );
 
+    // gRPC API
+    std::env::set_var("PROTOC", protobuf_src::protoc());
+    tonic_build::compile_protos("proto/solfees.proto")?;
+
+    // Bus messages
+    tonic_build::compile_protos("proto/bus.proto")?;
+
     Ok(())
 }
 
@@ -0,0 +1,180 @@
+use {
+    crate::{
+        auth::ApiKeys,
+        bus::{redis::RedisLastIdSaver, BusMessage},
+        cli,
+        config::ConfigBe,
+        grpc_server,
+        limits::Limits,
+        rpc_server,
+        rpc_solana::SolanaRpc,
+        tls::TlsAcceptor,
+    },
+    futures::future::{try_join_all, BoxFuture, FutureExt, TryFutureExt},
+    std::sync::Arc,
+    tokio::{
+        signal::unix::{signal, SignalKind},
+        sync::{mpsc, Notify},
+    },
+    tracing::{error, info, warn},
+};
+
+/// Run `solfees-be` servers with messages from the bus, `spawned_tasks` are expected to never finish,
+/// `last_id_saver` receives positions of applied messages
+pub async fn run_solfees_be(
+    mut config: ConfigBe,
+    mut bus_rx: mpsc::UnboundedReceiver<anyhow::Result<BusMessage>>,
+    last_id_saver: Option<RedisLastIdSaver>,
+    mut spawned_tasks: Vec<BoxFuture<'static, anyhow::Result<()>>>,
+) -> anyhow::Result<()> {
+    let api_keys = Arc::new(ApiKeys::new(config.auth).await?);
+    let admin_tls = TlsAcceptor::new_maybe(config.listen_admin.tls).await?;
+    let rpc_tls = TlsAcceptor::new_maybe(config.listen_rpc.tls.clone()).await?;
+    let grpc_tls = match &config.listen_grpc {
+        Some(config) => TlsAcceptor::new_maybe(config.tls.clone()).await?,
+        None => None,
+    };
+    let config_metrics = Arc::new(config.metrics);
+    let limits = Arc::new(Limits::new(config.limits));
+
+    let (solana_rpc, solana_rpc_futs) = SolanaRpc::new(
+        config.listen_rpc.request_calls_max,
+        std::mem::take(&mut config.listen_rpc.request_calls_weights),
+        config.listen_rpc.request_timeout,
+        config.listen_rpc.calls_queue_max,
+        config.listen_rpc.streams_channel_capacity,
+        config.listen_rpc.pool_size,
+    );
+    let solana_rpc_futs =
+        try_join_all(solana_rpc_futs.into_iter().enumerate().map(|(index, fut)| {
+            tokio::spawn(fut)
+                .map(|result| result?)
+                .map_err(move |error| {
+                    error.context(format!("SolanaRPC update loop#{index} failed"))
+                })
+        }))
+        .map_ok(|_vec| ())
+        .boxed();
+
+    let rpc_admin_shutdown = Arc::new(Notify::new());
+    let rpc_admin_fut = tokio::spawn(rpc_server::run_admin(
+        config.listen_admin.bind,
+        admin_tls.clone(),
+        None,
+        Arc::clone(&rpc_admin_shutdown),
+    ))
+    .map(|result| result?)
+    .map_err(|error| error.context("Admin RPC failed"))
+    .boxed();
+
+    let rpc_solfees_shutdown = Arc::new(Notify::new());
+    let rpc_solfees_fut = tokio::spawn(rpc_server::run_solfees(
+        config.listen_rpc,
+        solana_rpc.clone(),
+        Arc::clone(&config_metrics),
+        Arc::clone(&limits),
+        Arc::clone(&api_keys),
+        rpc_tls.clone(),
+        Arc::clone(&rpc_solfees_shutdown),
+    ))
+    .map(|result| result?)
+    .map_err(|error| error.context("Solfees RPC failed"))
+    .boxed();
+
+    spawned_tasks.extend([solana_rpc_futs, rpc_admin_fut, rpc_solfees_fut]);
+
+    let last_id_saver_shutdown = Arc::new(Notify::new());
+    if let Some(saver) = last_id_saver {
+        let last_id_saver_fut = tokio::spawn(saver.run(
+            solana_rpc.subscribe_position(),
+            Arc::clone(&last_id_saver_shutdown),
+        ))
+        .map(|result| result?)
+        .map_err(|error| error.context("Redis last ID saver failed"))
+        .boxed();
+        spawned_tasks.push(last_id_saver_fut);
+    }
+
+    let grpc_solfees_shutdown = Arc::new(Notify::new());
+    if let Some(config_grpc) = config.listen_grpc {
+        let grpc_solfees_fut = tokio::spawn(grpc_server::run_grpc(
+            config_grpc,
+            solana_rpc.clone(),
+            config_metrics,
+            limits,
+            Arc::clone(&api_keys),
+            grpc_tls.clone(),
+            Arc::clone(&grpc_solfees_shutdown),
+        ))
+        .map(|result| result?)
+        .map_err(|error| error.context("Solfees gRPC failed"))
+        .boxed();
+        spawned_tasks.push(grpc_solfees_fut);
+    }
+
+    let mut spawned_tasks = try_join_all(spawned_tasks);
+
+    let mut shutdown_rx = cli::shutdown_signal();
+    let sigint = SignalKind::interrupt();
+    let sigterm = SignalKind::terminate();
+    let mut sighup = signal(SignalKind::hangup())?;
+
+    loop {
+        tokio::select! {
+            signal = shutdown_rx.recv() => {
+                match signal {
+                    Some(signal) if signal == sigint => warn!("SIGINT received, exit..."),
+                    Some(signal) if signal == sigterm => warn!("SIGTERM received, exit..."),
+                    Some(signal) => warn!("unknown signal received ({signal:?}), exit..."),
+                    None => error!("shutdown channel is down"),
+                };
+                break;
+            }
+            value = &mut spawned_tasks => {
+                let _: Vec<()> = value?;
+                anyhow::bail!("spawned tasks finished");
+            }
+            _ = sighup.recv() => {
+                info!("SIGHUP received, reload API keys and TLS certificates");
+                if let Err(error) = api_keys.reload().await {
+                    error!(?error, "failed to reload API keys");
+                }
+                for tls in admin_tls.iter().chain(rpc_tls.iter()).chain(grpc_tls.iter()) {
+                    if let Err(error) = tls.reload().await {
+                        error!(?error, "failed to reload TLS certificates");
+                    }
+                }
+            }
+            value = bus_rx.recv() => {
+                if let Some(maybe_message) = value {
+                    solana_rpc.push_bus_message(maybe_message?).await?;
+                } else {
+                    error!("bus stream finished");
+                    break;
+                }
+            }
+        }
+    }
+
+    solana_rpc.shutdown();
+    rpc_admin_shutdown.notify_one();
+    rpc_solfees_shutdown.notify_one();
+    grpc_solfees_shutdown.notify_one();
+    last_id_saver_shutdown.notify_one();
+
+    tokio::select! {
+        signal = shutdown_rx.recv() => {
+            match signal {
+                Some(signal) if signal == sigint => warn!("SIGINT received, exit..."),
+                Some(signal) if signal == sigterm => warn!("SIGTERM received, exit..."),
+                Some(signal) => warn!("unknown signal received ({signal:?}), exit..."),
+                None => error!("shutdown channel is down"),
+            };
+        },
+        value = &mut spawned_tasks => {
+            let _: Vec<()> = value?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use {
+    crate::{config::ConfigAuth, rpc_solana::SolanaRpcMode},
+    anyhow::Context,
+    arc_swap::ArcSwap,
+    hyper::{header::HeaderMap, StatusCode},
+    serde::Deserialize,
+    std::{collections::HashMap, sync::Arc},
+    tokio::fs,
+    tracing::info,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ApiKeysFile {
+    keys: Vec<ApiKeysFileEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ApiKeysFileEntry {
+    key: String,
+    client_id: String,
+    #[serde(default)]
+    subscription_id: String,
+    #[serde(default)]
+    modes: Option<Vec<SolanaRpcMode>>,
+}
+
+#[derive(Debug)]
+pub struct ApiKey {
+    pub client_id: String,
+    pub subscription_id: String,
+    modes: Option<Vec<SolanaRpcMode>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthError {
+    MissingKey,
+    InvalidKey,
+    ModeNotAllowed,
+}
+
+impl AuthError {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::MissingKey => "API key is required",
+            Self::InvalidKey => "invalid API key",
+            Self::ModeNotAllowed => "API is not allowed for the key",
+        }
+    }
+
+    pub const fn status(self) -> StatusCode {
+        match self {
+            Self::MissingKey | Self::InvalidKey => StatusCode::UNAUTHORIZED,
+            Self::ModeNotAllowed => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+/// API keys loaded from `auth.keys_file`, authentication is disabled if file is not set
+#[derive(Debug)]
+pub struct ApiKeys {
+    config: ConfigAuth,
+    keys: ArcSwap<HashMap<String, Arc<ApiKey>>>,
+}
+
+impl ApiKeys {
+    pub async fn new(config: ConfigAuth) -> anyhow::Result<Self> {
+        let keys = Self {
+            config,
+            keys: ArcSwap::default(),
+        };
+        keys.reload().await?;
+        Ok(keys)
+    }
+
+    pub const fn is_enabled(&self) -> bool {
+        self.config.keys_file.is_some()
+    }
+
+    pub fn header(&self) -> Option<&str> {
+        self.is_enabled().then_some(self.config.header.as_str())
+    }
+
+    pub async fn reload(&self) -> anyhow::Result<()> {
+        let Some(path) = &self.config.keys_file else {
+            return Ok(());
+        };
+
+        let contents = fs::read(path)
+            .await
+            .with_context(|| format!("failed to read API keys file: {path:?}"))?;
+        let file: ApiKeysFile =
+            serde_yaml::from_slice(&contents).context("failed to parse API keys file")?;
+
+        let mut keys = HashMap::with_capacity(file.keys.len());
+        for entry in file.keys {
+            anyhow::ensure!(!entry.key.is_empty(), "empty API key is not allowed");
+            anyhow::ensure!(
+                !keys.contains_key(&entry.key),
+                "duplicated API key for client: {}",
+                entry.client_id
+            );
+            let api_key = Arc::new(ApiKey {
+                client_id: entry.client_id,
+                subscription_id: entry.subscription_id,
+                modes: entry.modes,
+            });
+            keys.insert(entry.key, api_key);
+        }
+
+        info!(total = keys.len(), "API keys loaded");
+        self.keys.store(Arc::new(keys));
+        Ok(())
+    }
+
+    /// Split path `/{key}/api/...` to key and path
+    pub fn split_path<'a>(&self, path: &'a str) -> Option<(&'a str, &'a str)> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let path = path.strip_prefix('/')?;
+        let index = path.find('/')?;
+        let (key, path) = path.split_at(index);
+        (!key.is_empty()).then_some((key, path))
+    }
+
+    /// Key is searched in the path, header and query string (in that order)
+    pub fn authorize(
+        &self,
+        path_key: Option<&str>,
+        headers: &HeaderMap,
+        query: Option<&str>,
+        mode: SolanaRpcMode,
+    ) -> Result<Option<Arc<ApiKey>>, AuthError> {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+
+        let query_key = query.and_then(|query| {
+            form_urlencoded::parse(query.as_bytes())
+                .find(|(name, _value)| name == self.config.query.as_str())
+                .map(|(_name, value)| value.into_owned())
+        });
+        let key = path_key
+            .or_else(|| {
+                headers
+                    .get(self.config.header.as_str())
+                    .and_then(|value| value.to_str().ok())
+            })
+            .or(query_key.as_deref())
+            .ok_or(AuthError::MissingKey)?;
+
+        let api_key = self
+            .keys
+            .load()
+            .get(key)
+            .cloned()
+            .ok_or(AuthError::InvalidKey)?;
+        if let Some(modes) = &api_key.modes {
+            if !modes.contains(&mode) {
+                return Err(AuthError::ModeNotAllowed);
+            }
+        }
+
+        Ok(Some(api_key))
+    }
+}
@@ -0,0 +1,45 @@
+use {
+    futures::future::{FutureExt, TryFutureExt},
+    solfees_be::{
+        app,
+        bus::{self, memory::MemoryBus},
+        cli,
+        config::{ConfigAllInOne as Config, ConfigBe},
+        metrics,
+    },
+};
+
+fn main() -> anyhow::Result<()> {
+    cli::run_main(
+        metrics::init_all_in_one,
+        |id| format!("solfees-{id:02}"),
+        main2,
+    )
+}
+
+async fn main2(config: Config) -> anyhow::Result<()> {
+    let bus = MemoryBus::new(config.memory.maxlen, config.memory.channel_capacity);
+    let bus_rx = bus::subscribe(Box::new(bus.consumer()))?;
+
+    let publish_fut = tokio::spawn(bus::publish(Box::new(bus), config.grpc, config.rpc, false))
+        .map(|result| -> anyhow::Result<()> {
+            result??;
+            anyhow::bail!("bus publisher finished")
+        })
+        .map_err(|error| error.context("bus publisher failed"))
+        .boxed();
+
+    let config = ConfigBe {
+        tracing: config.tracing,
+        bus: Default::default(),
+        redis: Default::default(),
+        unix: Default::default(),
+        listen_admin: config.listen_admin,
+        listen_rpc: config.listen_rpc,
+        listen_grpc: config.listen_grpc,
+        metrics: config.metrics,
+        limits: config.limits,
+        auth: config.auth,
+    };
+    app::run_solfees_be(config, bus_rx, None, vec![publish_fut]).await
+}
@@ -1,10 +1,11 @@
 use {
     anyhow::Context,
-    clap::Parser,
+    clap::{Parser, ValueEnum},
     futures::{future::TryFutureExt, stream::StreamExt},
     jsonrpc_core::Success as RpcSuccess,
     serde::Serialize,
     solfees_be::rpc_solana::SlotsSubscribeOutput,
+    std::io::Read,
     tokio_tungstenite::{connect_async, tungstenite::protocol::Message},
     tracing::{error, info},
 };
@@ -30,6 +31,43 @@ struct Args {
     /// Skip transactions with zero unit price
     #[clap(long, default_value_t = false)]
     skip_zeros: bool,
+
+    /// Request compression of large messages (received as binary)
+    #[clap(long)]
+    compression: Option<Compression>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Compression {
+    Zstd,
+    Br,
+    Gzip,
+}
+
+impl Compression {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Zstd => "zstd",
+            Self::Br => "br",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    fn decode(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::Zstd => zstd::stream::decode_all(data),
+            Self::Br => {
+                let mut decoded = vec![];
+                brotli::Decompressor::new(data, 4096).read_to_end(&mut decoded)?;
+                Ok(decoded)
+            }
+            Self::Gzip => {
+                let mut decoded = vec![];
+                flate2::read::GzDecoder::new(data).read_to_end(&mut decoded)?;
+                Ok(decoded)
+            }
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -58,7 +96,22 @@ async fn main() -> anyhow::Result<()> {
     }))
     .context("failed to create request")?;
 
-    let (ws_stream, _) = connect_async(args.endpoint)
+    let endpoint = match args.compression {
+        Some(compression) => {
+            let separator = if args.endpoint.contains('?') {
+                '&'
+            } else {
+                '?'
+            };
+            format!(
+                "{}{separator}compression={}",
+                args.endpoint,
+                compression.as_str()
+            )
+        }
+        None => args.endpoint,
+    };
+    let (ws_stream, _) = connect_async(endpoint)
         .await
         .context("failed to connect to WS server")?;
     let (ws_write, mut ws_read) = ws_stream.split();
@@ -71,8 +124,16 @@ async fn main() -> anyhow::Result<()> {
         loop {
             let text = match ws_read.next().await {
                 Some(Ok(Message::Text(message))) => message,
-                Some(Ok(Message::Binary(msg))) => String::from_utf8(msg)
-                    .map_err(|_error| anyhow::anyhow!("failed to convert to string"))?,
+                Some(Ok(Message::Binary(msg))) => {
+                    let msg = match args.compression {
+                        Some(compression) => compression
+                            .decode(&msg)
+                            .context("failed to decompress message")?,
+                        None => msg,
+                    };
+                    String::from_utf8(msg)
+                        .map_err(|_error| anyhow::anyhow!("failed to convert to string"))?
+                }
                 Some(Ok(Message::Ping(_))) => continue,
                 Some(Ok(Message::Pong(_))) => continue,
                 Some(Ok(Message::Frame(_))) => continue,
@@ -70,6 +70,9 @@ async fn main() -> anyhow::Result<()> {
                     }
                 }
             }
+            GeyserMessage::SlotSummary(_) => {
+                // not produced by gRPC subscription
+            }
         }
     }
 
@@ -0,0 +1,270 @@
+use {
+    crate::{
+        config::{ConfigGrpc, ConfigRpc},
+        grpc_geyser::{self, CommitmentLevel, GeyserMessage},
+        metrics::grpc2redis as metrics,
+        schedule::{LeaderScheduleRpc, LeadersSchedule, LeadersScheduleSolfees},
+    },
+    anyhow::Context,
+    futures::future::BoxFuture,
+    jsonrpc_core::Value as JsonrpcValue,
+    lru::LruCache,
+    solana_sdk::{clock::Epoch, epoch_schedule::EpochSchedule},
+    std::{collections::HashSet, num::NonZeroUsize, sync::Arc},
+    tokio::sync::mpsc,
+    tracing::error,
+};
+
+pub mod codec;
+pub mod memory;
+pub mod redis;
+pub mod unix;
+
+/// Writing side of the bus, used by `solfees-grpc2redis`
+pub trait Publisher: Send {
+    /// Leader schedules already saved in the bus
+    fn get_epochs(&mut self) -> BoxFuture<'_, anyhow::Result<Vec<(Epoch, LeaderScheduleRpc)>>>;
+
+    fn push_epoch(
+        &mut self,
+        epoch: Epoch,
+        schedule: LeaderScheduleRpc,
+    ) -> BoxFuture<'_, anyhow::Result<()>>;
+
+    /// Push messages not older than finalized slot in the bus, returns pushed messages
+    fn push_messages(
+        &mut self,
+        messages: Vec<GeyserMessage>,
+    ) -> BoxFuture<'_, anyhow::Result<Vec<GeyserMessage>>>;
+}
+
+/// Reading side of the bus, used by `solfees-be`
+pub trait Consumer: Send {
+    /// Leader schedules for requested epochs (all if `None`), unknown epochs are skipped
+    fn get_epochs(
+        &mut self,
+        epochs: Option<Vec<Epoch>>,
+    ) -> BoxFuture<'_, anyhow::Result<Vec<(Epoch, LeaderScheduleRpc)>>>;
+
+    /// Wait for next messages, never returns empty `Vec`
+    fn read(&mut self) -> BoxFuture<'_, anyhow::Result<Vec<GeyserMessage>>>;
+
+    /// Position after the last `read`, passed as `BusMessage::Position` after messages
+    fn position(&self) -> Option<String> {
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum BusMessage {
+    Geyser(GeyserMessage),
+    Epoch {
+        epoch: Epoch,
+        leader_schedule_solfees: Arc<JsonrpcValue>, // serialized `LeadersScheduleSolfees`
+        leader_schedule_rpc: Arc<JsonrpcValue>,     // serialized `HashMap<String, Vec<usize>>`
+    },
+    /// Position of the consumer, all previous messages are already received
+    Position(String),
+}
+
+impl BusMessage {
+    fn build_epoch(epoch: Epoch, leader_schedule_rpc: &LeaderScheduleRpc) -> anyhow::Result<Self> {
+        let leader_schedule_solfees: LeadersScheduleSolfees =
+            LeadersSchedule::new(leader_schedule_rpc)
+                .with_context(|| format!("failed to build schedule for epoch {epoch}"))?
+                .into();
+
+        let leader_schedule_solfees =
+            serde_json::to_value(&leader_schedule_solfees).expect("failed to serialize");
+        let leader_schedule_rpc =
+            serde_json::to_value(leader_schedule_rpc).expect("failed to serialize");
+
+        Ok(Self::Epoch {
+            epoch,
+            leader_schedule_solfees: Arc::new(leader_schedule_solfees),
+            leader_schedule_rpc: Arc::new(leader_schedule_rpc),
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct SeenSlot {
+    slot: bool,
+    processed: bool,
+    confirmed: bool,
+    finalized: bool,
+}
+
+pub fn subscribe(
+    mut consumer: Box<dyn Consumer>,
+) -> anyhow::Result<mpsc::UnboundedReceiver<anyhow::Result<BusMessage>>> {
+    let mut epochs = HashSet::new();
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut lru = LruCache::new(
+        NonZeroUsize::new(8_192).ok_or(anyhow::anyhow!("failed to create LRU capacity"))?,
+    );
+    tokio::spawn(async move {
+        let epoch_schedule = EpochSchedule::custom(432_000, 432_000, false);
+        let mut epochs_queue = vec![];
+        let mut epochs_fetch_all = true;
+        let mut finalized_slot_tip = 0;
+        loop {
+            if epochs_fetch_all || !epochs_queue.is_empty() {
+                let request = if epochs_fetch_all {
+                    None
+                } else {
+                    Some(std::mem::take(&mut epochs_queue))
+                };
+                epochs_fetch_all = false;
+
+                match consumer.get_epochs(request).await {
+                    Ok(value) => {
+                        for (epoch, leader_schedule_rpc) in value {
+                            let msg = BusMessage::build_epoch(epoch, &leader_schedule_rpc);
+
+                            let alive = msg.is_ok();
+                            if tx.send(msg).is_err() || !alive {
+                                return;
+                            }
+
+                            epochs.insert(epoch);
+                        }
+                    }
+                    Err(error) => {
+                        let _ = tx.send(Err(error));
+                        return;
+                    }
+                }
+            }
+
+            let messages = match consumer.read().await {
+                Ok(messages) => messages,
+                Err(error) => {
+                    let _ = tx.send(Err(error));
+                    return;
+                }
+            };
+
+            for message in messages {
+                // deduplication
+                let (slot, commitment) = match &message {
+                    GeyserMessage::Status { slot, commitment } => (*slot, Some(*commitment)),
+                    GeyserMessage::Slot { slot, .. } => (*slot, None),
+                    GeyserMessage::SlotSummary(summary) => (summary.slot, None),
+                };
+                if slot < finalized_slot_tip {
+                    continue;
+                }
+                if commitment == Some(CommitmentLevel::Finalized) {
+                    finalized_slot_tip = slot;
+                }
+
+                let entry = lru.get_or_insert_mut(slot, SeenSlot::default);
+                let seen = match commitment {
+                    Some(CommitmentLevel::Processed) => &mut entry.processed,
+                    Some(CommitmentLevel::Confirmed) => &mut entry.confirmed,
+                    Some(CommitmentLevel::Finalized) => &mut entry.finalized,
+                    None => &mut entry.slot,
+                };
+                if *seen {
+                    continue;
+                }
+                *seen = true;
+
+                let epoch = epoch_schedule.get_epoch(slot);
+                if commitment.is_none()
+                    && !epochs.contains(&epoch)
+                    && !epochs_queue.contains(&epoch)
+                {
+                    epochs_queue.push(epoch);
+                }
+
+                if tx.send(Ok(BusMessage::Geyser(message))).is_err() {
+                    return;
+                }
+            }
+
+            if let Some(position) = consumer.position() {
+                if tx.send(Ok(BusMessage::Position(position))).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(rx)
+}
+
+/// Receive messages from gRPC and push them to the bus until one of streams is finished,
+/// with `slot_summary` slots are pushed as `GeyserMessage::SlotSummary`
+pub async fn publish(
+    mut publisher: Box<dyn Publisher>,
+    config_grpc: ConfigGrpc,
+    config_rpc: ConfigRpc,
+    slot_summary: bool,
+) -> anyhow::Result<()> {
+    let saved_epochs = publisher
+        .get_epochs()
+        .await
+        .context("failed to fetch saved epochs")?;
+
+    let (mut geyser_rx, mut schedule_rx) = grpc_geyser::subscribe(
+        config_grpc.endpoint,
+        config_grpc.x_token,
+        config_rpc.endpoint,
+        saved_epochs,
+    )
+    .await
+    .context("failed to open gRPC subscription")?;
+
+    loop {
+        let mut messages = tokio::select! {
+            value = geyser_rx.recv() => {
+                if let Some(maybe_message) = value {
+                    vec![maybe_message?]
+                } else {
+                    error!("geyser stream finished");
+                    return Ok(());
+                }
+            }
+            value = schedule_rx.recv() => {
+                let Some((epoch, schedule)) = value else {
+                    error!("schedule stream finished");
+                    return Ok(());
+                };
+
+                publisher
+                    .push_epoch(epoch, schedule)
+                    .await
+                    .context("failed to push epoch schedule")?;
+
+                continue;
+            }
+        };
+
+        // trying to fetch all messages
+        while let Ok(maybe_message) = geyser_rx.try_recv() {
+            messages.push(maybe_message?);
+        }
+
+        if slot_summary {
+            messages = messages
+                .into_iter()
+                .map(GeyserMessage::into_summary)
+                .collect();
+        }
+
+        let messages = publisher
+            .push_messages(messages)
+            .await
+            .context("failed to push messages")?;
+
+        metrics::redis_messages_pushed_inc_by(messages.len());
+        for message in messages {
+            if let GeyserMessage::Status { slot, commitment } = message {
+                metrics::redis_slot_pushed_set(commitment, slot);
+            }
+        }
+    }
+}
@@ -0,0 +1,583 @@
+use {
+    crate::{
+        config::ConfigBusCompression,
+        grpc_geyser::{
+            CommitmentLevel, GeyserMessage, GeyserTransaction, GeyserTransactionAccounts,
+            SlotSummary, SlotSummaryTransaction,
+        },
+    },
+    anyhow::Context,
+    prost::Message,
+    solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Signature},
+    std::{
+        collections::{HashMap, HashSet},
+        io::Read,
+        sync::Arc,
+    },
+};
+
+pub mod proto {
+    #![allow(
+        clippy::clone_on_ref_ptr,
+        clippy::missing_const_for_fn,
+        clippy::trivially_copy_pass_by_ref
+    )]
+
+    tonic::include_proto!("solfees.bus");
+}
+
+/// Raw bincode of `GeyserMessage` without version byte
+pub const VERSION_LEGACY: u8 = 1;
+/// Protobuf encoded `GeyserMessage`, see `proto/bus.proto`
+pub const VERSION_PROTOBUF: u8 = 2;
+/// Optionally compressed protobuf with accounts dictionary per slot
+pub const VERSION_COMPRESSED: u8 = 3;
+
+const COMPRESSION_NONE: u8 = 0;
+const COMPRESSION_ZSTD: u8 = 1;
+const COMPRESSION_LZ4: u8 = 2;
+
+const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024; // 64MiB
+
+pub fn encode(
+    message: &GeyserMessage,
+    version: u8,
+    compression: ConfigBusCompression,
+) -> anyhow::Result<Vec<u8>> {
+    match version {
+        // first byte of `SlotSummary` in bincode is the same as version of protobuf message
+        VERSION_LEGACY if matches!(message, GeyserMessage::SlotSummary(_)) => {
+            anyhow::bail!("slot summary can not be encoded with legacy version")
+        }
+        VERSION_LEGACY => bincode::serialize(message).context("failed to serialize GeyserMessage"),
+        VERSION_PROTOBUF => {
+            let message = encode_proto(message, false);
+            let mut data = Vec::with_capacity(1 + message.encoded_len());
+            data.push(VERSION_PROTOBUF);
+            message.encode(&mut data).expect("enough capacity");
+            Ok(data)
+        }
+        VERSION_COMPRESSED => {
+            let message = encode_proto(message, true).encode_to_vec();
+            let (compression, compressed) = match compression {
+                ConfigBusCompression::None => (COMPRESSION_NONE, message),
+                ConfigBusCompression::Zstd => (
+                    COMPRESSION_ZSTD,
+                    zstd::bulk::compress(&message, zstd::DEFAULT_COMPRESSION_LEVEL)
+                        .context("failed to compress message with zstd")?,
+                ),
+                ConfigBusCompression::Lz4 => {
+                    (COMPRESSION_LZ4, lz4_flex::compress_prepend_size(&message))
+                }
+            };
+            let mut data = Vec::with_capacity(2 + compressed.len());
+            data.push(VERSION_COMPRESSED);
+            data.push(compression);
+            data.extend_from_slice(&compressed);
+            Ok(data)
+        }
+        version => anyhow::bail!("unknown message version: {version}"),
+    }
+}
+
+pub fn decode(data: &[u8]) -> anyhow::Result<GeyserMessage> {
+    match data.first().copied() {
+        // first byte of legacy message is enum variant (`u32` little endian)
+        Some(0 | VERSION_LEGACY) => {
+            bincode::deserialize(data).context("failed to decode legacy message")
+        }
+        Some(VERSION_PROTOBUF) => decode_proto(
+            proto::GeyserMessage::decode(&data[1..])
+                .context("failed to decode protobuf message")?,
+        ),
+        Some(VERSION_COMPRESSED) => {
+            let Some((compression, data)) = data[1..].split_first() else {
+                anyhow::bail!("compression is not defined");
+            };
+            let data = decompress(*compression, data)?;
+            decode_proto(
+                proto::GeyserMessage::decode(data.as_slice())
+                    .context("failed to decode protobuf message")?,
+            )
+        }
+        Some(version) => anyhow::bail!("unknown message version: {version}"),
+        None => anyhow::bail!("empty message"),
+    }
+}
+
+fn decompress(compression: u8, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    match compression {
+        COMPRESSION_NONE => Ok(data.to_vec()),
+        COMPRESSION_ZSTD => {
+            let mut decompressed = vec![];
+            zstd::stream::read::Decoder::new(data)
+                .context("failed to create zstd decoder")?
+                .take(MAX_DECOMPRESSED_SIZE as u64 + 1)
+                .read_to_end(&mut decompressed)
+                .context("failed to decompress zstd message")?;
+            anyhow::ensure!(
+                decompressed.len() <= MAX_DECOMPRESSED_SIZE,
+                "decompressed message is too large"
+            );
+            Ok(decompressed)
+        }
+        COMPRESSION_LZ4 => {
+            let size = data
+                .get(0..4)
+                .and_then(|size| size.try_into().ok())
+                .map(u32::from_le_bytes)
+                .context("lz4 size is not defined")?;
+            anyhow::ensure!(
+                size as usize <= MAX_DECOMPRESSED_SIZE,
+                "decompressed message is too large"
+            );
+            lz4_flex::decompress_size_prepended(data).context("failed to decompress lz4 message")
+        }
+        compression => anyhow::bail!("unknown compression: {compression}"),
+    }
+}
+
+impl From<CommitmentLevel> for proto::CommitmentLevel {
+    fn from(commitment: CommitmentLevel) -> Self {
+        match commitment {
+            CommitmentLevel::Processed => Self::Processed,
+            CommitmentLevel::Confirmed => Self::Confirmed,
+            CommitmentLevel::Finalized => Self::Finalized,
+        }
+    }
+}
+
+impl From<proto::CommitmentLevel> for CommitmentLevel {
+    fn from(commitment: proto::CommitmentLevel) -> Self {
+        match commitment {
+            proto::CommitmentLevel::Processed => Self::Processed,
+            proto::CommitmentLevel::Confirmed => Self::Confirmed,
+            proto::CommitmentLevel::Finalized => Self::Finalized,
+        }
+    }
+}
+
+fn encode_proto(message: &GeyserMessage, with_dictionary: bool) -> proto::GeyserMessage {
+    let message = match message {
+        GeyserMessage::Status { slot, commitment } => {
+            proto::geyser_message::Message::Status(proto::SlotStatus {
+                slot: *slot,
+                commitment: proto::CommitmentLevel::from(*commitment) as i32,
+            })
+        }
+        GeyserMessage::Slot {
+            leader,
+            slot,
+            hash,
+            time,
+            height,
+            parent_slot,
+            parent_hash,
+            transactions,
+        } => {
+            let dictionary = with_dictionary.then(|| AccountsDictionary::new(transactions));
+            proto::geyser_message::Message::Slot(proto::Slot {
+                leader: leader.map(|leader| leader.to_bytes().to_vec()),
+                slot: *slot,
+                hash: hash.to_bytes().to_vec(),
+                time: *time,
+                height: *height,
+                parent_slot: *parent_slot,
+                parent_hash: parent_hash.to_bytes().to_vec(),
+                transactions: transactions
+                    .iter()
+                    .map(|tx| encode_transaction(tx, dictionary.as_ref()))
+                    .collect(),
+                accounts: dictionary
+                    .map(|dictionary| encode_pubkeys(dictionary.accounts.iter()))
+                    .unwrap_or_default(),
+            })
+        }
+        GeyserMessage::SlotSummary(summary) => {
+            proto::geyser_message::Message::Summary(encode_summary(summary))
+        }
+    };
+    proto::GeyserMessage {
+        message: Some(message),
+    }
+}
+
+fn decode_proto(message: proto::GeyserMessage) -> anyhow::Result<GeyserMessage> {
+    Ok(match message.message.context("message is not defined")? {
+        proto::geyser_message::Message::Status(status) => GeyserMessage::Status {
+            slot: status.slot,
+            commitment: proto::CommitmentLevel::try_from(status.commitment)
+                .context("invalid commitment")?
+                .into(),
+        },
+        proto::geyser_message::Message::Slot(slot) => {
+            let accounts = slot
+                .accounts
+                .iter()
+                .map(|pubkey| decode_pubkey(pubkey))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            GeyserMessage::Slot {
+                leader: slot.leader.as_deref().map(decode_pubkey).transpose()?,
+                slot: slot.slot,
+                hash: Hash::new_from_array(decode_array(&slot.hash, "hash")?),
+                time: slot.time,
+                height: slot.height,
+                parent_slot: slot.parent_slot,
+                parent_hash: Hash::new_from_array(decode_array(&slot.parent_hash, "hash")?),
+                transactions: Arc::new(
+                    slot.transactions
+                        .into_iter()
+                        .map(|tx| decode_transaction(tx, &accounts))
+                        .collect::<anyhow::Result<_>>()?,
+                ),
+            }
+        }
+        proto::geyser_message::Message::Summary(summary) => {
+            GeyserMessage::SlotSummary(Arc::new(decode_summary(summary)?))
+        }
+    })
+}
+
+fn encode_summary(summary: &SlotSummary) -> proto::SlotSummary {
+    let mut counts = HashMap::<Pubkey, usize>::new();
+    for (account, fees) in summary
+        .writable_fees
+        .iter()
+        .chain(summary.readable_fees.iter())
+    {
+        *counts.entry(*account).or_default() += fees.len();
+    }
+    let dictionary = AccountsDictionary::from_counts(counts);
+
+    let encode_fees = |map: &HashMap<Pubkey, Vec<u64>>| {
+        map.iter()
+            .map(|(account, fees)| proto::SlotSummaryAccountFees {
+                account_idx: dictionary.indexes[account],
+                fees: fees.clone(),
+            })
+            .collect()
+    };
+    let encode_accounts = |accounts: &[Pubkey]| {
+        accounts
+            .iter()
+            .map(|account| dictionary.indexes[account])
+            .collect()
+    };
+
+    proto::SlotSummary {
+        leader: summary.leader.map(|leader| leader.to_bytes().to_vec()),
+        slot: summary.slot,
+        hash: summary.hash.to_bytes().to_vec(),
+        time: summary.time,
+        height: summary.height,
+        parent_slot: summary.parent_slot,
+        parent_hash: summary.parent_hash.to_bytes().to_vec(),
+        total_transactions: summary.total_transactions,
+        total_transactions_vote: summary.total_transactions_vote,
+        total_fee: summary.total_fee,
+        total_units_consumed: summary.total_units_consumed,
+        fees: summary.fees.clone(),
+        accounts: encode_pubkeys(dictionary.accounts.iter()),
+        writable_fees: encode_fees(&summary.writable_fees),
+        readable_fees: encode_fees(&summary.readable_fees),
+        transactions: summary
+            .transactions
+            .iter()
+            .map(|tx| proto::SlotSummaryTransaction {
+                writable_idx: encode_accounts(&tx.writable),
+                readable_idx: encode_accounts(&tx.readable),
+            })
+            .collect(),
+    }
+}
+
+fn decode_summary(summary: proto::SlotSummary) -> anyhow::Result<SlotSummary> {
+    let accounts = summary
+        .accounts
+        .iter()
+        .map(|pubkey| decode_pubkey(pubkey))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let lookup = |index: u32| {
+        accounts
+            .get(index as usize)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("invalid account index: {index}"))
+    };
+    let decode_fees = |fees: Vec<proto::SlotSummaryAccountFees>| {
+        fees.into_iter()
+            .map(|value| -> anyhow::Result<_> { Ok((lookup(value.account_idx)?, value.fees)) })
+            .collect::<anyhow::Result<HashMap<_, _>>>()
+    };
+    let decode_accounts = |indexes: Vec<u32>| {
+        indexes
+            .into_iter()
+            .map(lookup)
+            .collect::<anyhow::Result<Vec<_>>>()
+    };
+
+    Ok(SlotSummary {
+        leader: summary.leader.as_deref().map(decode_pubkey).transpose()?,
+        slot: summary.slot,
+        hash: Hash::new_from_array(decode_array(&summary.hash, "hash")?),
+        time: summary.time,
+        height: summary.height,
+        parent_slot: summary.parent_slot,
+        parent_hash: Hash::new_from_array(decode_array(&summary.parent_hash, "hash")?),
+        total_transactions: summary.total_transactions,
+        total_transactions_vote: summary.total_transactions_vote,
+        total_fee: summary.total_fee,
+        total_units_consumed: summary.total_units_consumed,
+        fees: summary.fees,
+        writable_fees: decode_fees(summary.writable_fees)?,
+        readable_fees: decode_fees(summary.readable_fees)?,
+        transactions: summary
+            .transactions
+            .into_iter()
+            .map(|tx| -> anyhow::Result<_> {
+                Ok(SlotSummaryTransaction::new(
+                    decode_accounts(tx.writable_idx)?,
+                    decode_accounts(tx.readable_idx)?,
+                ))
+            })
+            .collect::<anyhow::Result<_>>()?,
+    })
+}
+
+// accounts used in the slot, sorted by number of usages
+struct AccountsDictionary {
+    accounts: Vec<Pubkey>,
+    indexes: HashMap<Pubkey, u32>,
+}
+
+impl AccountsDictionary {
+    fn new(transactions: &[GeyserTransaction]) -> Self {
+        let mut counts = HashMap::<Pubkey, usize>::new();
+        for tx in transactions {
+            for pubkey in tx
+                .accounts
+                .writable
+                .iter()
+                .chain(tx.accounts.readable.iter())
+                .chain(tx.accounts.signers.iter())
+                .chain(std::iter::once(&tx.accounts.fee_payer))
+            {
+                *counts.entry(*pubkey).or_default() += 1;
+            }
+        }
+        Self::from_counts(counts)
+    }
+
+    fn from_counts(counts: HashMap<Pubkey, usize>) -> Self {
+        let mut accounts = counts.into_iter().collect::<Vec<_>>();
+        accounts.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let accounts = accounts
+            .into_iter()
+            .map(|(pubkey, _count)| pubkey)
+            .collect::<Vec<_>>();
+        let indexes = accounts
+            .iter()
+            .enumerate()
+            .map(|(index, pubkey)| (*pubkey, index as u32))
+            .collect();
+
+        Self { accounts, indexes }
+    }
+
+    fn encode(&self, pubkeys: &HashSet<Pubkey>) -> Vec<u32> {
+        pubkeys.iter().map(|pubkey| self.indexes[pubkey]).collect()
+    }
+}
+
+fn encode_transaction(
+    tx: &GeyserTransaction,
+    dictionary: Option<&AccountsDictionary>,
+) -> proto::Transaction {
+    let mut value = proto::Transaction {
+        signature: tx.signature.as_ref().to_vec(),
+        vote: tx.vote,
+        sigs_count: tx.sigs_count,
+        unit_limit: tx.unit_limit,
+        unit_price: tx.unit_price,
+        units_consumed: tx.units_consumed,
+        fee: tx.fee,
+        ..Default::default()
+    };
+    if let Some(dictionary) = dictionary {
+        value.accounts_writable_idx = dictionary.encode(&tx.accounts.writable);
+        value.accounts_readable_idx = dictionary.encode(&tx.accounts.readable);
+        value.accounts_signers_idx = dictionary.encode(&tx.accounts.signers);
+        value.fee_payer_idx = dictionary.indexes[&tx.accounts.fee_payer];
+    } else {
+        value.accounts_writable = encode_pubkeys(tx.accounts.writable.iter());
+        value.accounts_readable = encode_pubkeys(tx.accounts.readable.iter());
+        value.accounts_signers = encode_pubkeys(tx.accounts.signers.iter());
+        value.fee_payer = tx.accounts.fee_payer.to_bytes().to_vec();
+    }
+    value
+}
+
+// accounts are taken from the dictionary if `fee_payer` is not set
+fn decode_transaction(
+    tx: proto::Transaction,
+    dictionary: &[Pubkey],
+) -> anyhow::Result<GeyserTransaction> {
+    let accounts = if tx.fee_payer.is_empty() {
+        let lookup = |index: u32| {
+            dictionary
+                .get(index as usize)
+                .copied()
+                .ok_or_else(|| anyhow::anyhow!("invalid account index: {index}"))
+        };
+        let lookup_all = |indexes: Vec<u32>| {
+            indexes
+                .into_iter()
+                .map(lookup)
+                .collect::<anyhow::Result<HashSet<_>>>()
+        };
+        GeyserTransactionAccounts {
+            writable: lookup_all(tx.accounts_writable_idx)?,
+            readable: lookup_all(tx.accounts_readable_idx)?,
+            signers: lookup_all(tx.accounts_signers_idx)?,
+            fee_payer: lookup(tx.fee_payer_idx)?,
+        }
+    } else {
+        GeyserTransactionAccounts {
+            writable: decode_pubkeys(tx.accounts_writable)?,
+            readable: decode_pubkeys(tx.accounts_readable)?,
+            signers: decode_pubkeys(tx.accounts_signers)?,
+            fee_payer: decode_pubkey(&tx.fee_payer)?,
+        }
+    };
+
+    Ok(GeyserTransaction {
+        signature: Signature::from(decode_array::<64>(&tx.signature, "signature")?),
+        vote: tx.vote,
+        accounts,
+        sigs_count: tx.sigs_count,
+        unit_limit: tx.unit_limit,
+        unit_price: tx.unit_price,
+        units_consumed: tx.units_consumed,
+        fee: tx.fee,
+    })
+}
+
+fn decode_array<const N: usize>(data: &[u8], name: &str) -> anyhow::Result<[u8; N]> {
+    data.try_into()
+        .map_err(|_| anyhow::anyhow!("invalid {name} length: {}", data.len()))
+}
+
+fn decode_pubkey(data: &[u8]) -> anyhow::Result<Pubkey> {
+    decode_array(data, "pubkey").map(Pubkey::new_from_array)
+}
+
+fn encode_pubkeys<'a>(pubkeys: impl Iterator<Item = &'a Pubkey>) -> Vec<Vec<u8>> {
+    pubkeys.map(|pubkey| pubkey.to_bytes().to_vec()).collect()
+}
+
+fn decode_pubkeys(pubkeys: Vec<Vec<u8>>) -> anyhow::Result<HashSet<Pubkey>> {
+    pubkeys
+        .into_iter()
+        .map(|pubkey| decode_pubkey(&pubkey))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_slot() -> GeyserMessage {
+        let accounts = (0..4).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        let create_tx =
+            |index: u8, vote: bool, writable: &[Pubkey], readable: &[Pubkey]| GeyserTransaction {
+                signature: Signature::from([index; 64]),
+                vote,
+                accounts: GeyserTransactionAccounts {
+                    writable: writable.iter().copied().collect(),
+                    readable: readable.iter().copied().collect(),
+                    signers: HashSet::from([writable[0]]),
+                    fee_payer: writable[0],
+                },
+                sigs_count: 1,
+                unit_limit: 200_000,
+                unit_price: u64::from(index) * 1_000,
+                units_consumed: Some(150),
+                fee: 5_000,
+            };
+
+        GeyserMessage::Slot {
+            leader: Some(Pubkey::new_unique()),
+            slot: 42,
+            hash: Hash::new_unique(),
+            time: 1_700_000_000,
+            height: 40,
+            parent_slot: 41,
+            parent_hash: Hash::new_unique(),
+            transactions: Arc::new(vec![
+                create_tx(1, false, &accounts[0..2], &accounts[2..3]),
+                create_tx(2, false, &accounts[1..3], &accounts[3..4]),
+                create_tx(3, true, &accounts[3..4], &[]),
+            ]),
+        }
+    }
+
+    fn create_messages() -> Vec<GeyserMessage> {
+        vec![
+            GeyserMessage::Status {
+                slot: 42,
+                commitment: CommitmentLevel::Confirmed,
+            },
+            create_slot(),
+        ]
+    }
+
+    fn roundtrip(message: &GeyserMessage, version: u8, compression: ConfigBusCompression) {
+        let data = encode(message, version, compression).expect("encoded");
+        let decoded = decode(&data).expect("decoded");
+        assert_eq!(&decoded, message, "version {version}, {compression:?}");
+    }
+
+    #[test]
+    fn roundtrip_legacy() {
+        for message in create_messages() {
+            roundtrip(&message, VERSION_LEGACY, ConfigBusCompression::None);
+        }
+
+        let summary = create_slot().into_summary();
+        assert!(encode(&summary, VERSION_LEGACY, ConfigBusCompression::None).is_err());
+    }
+
+    #[test]
+    fn roundtrip_protobuf() {
+        for message in create_messages() {
+            roundtrip(&message, VERSION_PROTOBUF, ConfigBusCompression::None);
+        }
+        roundtrip(
+            &create_slot().into_summary(),
+            VERSION_PROTOBUF,
+            ConfigBusCompression::None,
+        );
+    }
+
+    #[test]
+    fn roundtrip_compressed() {
+        let mut messages = create_messages();
+        messages.push(create_slot().into_summary());
+        for compression in [
+            ConfigBusCompression::None,
+            ConfigBusCompression::Zstd,
+            ConfigBusCompression::Lz4,
+        ] {
+            for message in messages.iter() {
+                roundtrip(message, VERSION_COMPRESSED, compression);
+            }
+        }
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert!(decode(&[]).is_err());
+        assert!(decode(&[u8::MAX]).is_err());
+        assert!(decode(&[VERSION_COMPRESSED]).is_err());
+        assert!(decode(&[VERSION_COMPRESSED, u8::MAX, 0]).is_err());
+    }
+}
@@ -0,0 +1,261 @@
+use {
+    crate::{
+        bus::{Consumer, Publisher},
+        grpc_geyser::{CommitmentLevel, GeyserMessage},
+        schedule::LeaderScheduleRpc,
+    },
+    futures::future::{BoxFuture, FutureExt},
+    solana_sdk::clock::{Epoch, Slot},
+    std::{
+        collections::{BTreeMap, VecDeque},
+        sync::{Arc, Mutex},
+    },
+    tokio::sync::broadcast,
+};
+
+#[derive(Debug, Clone)]
+pub enum MemoryBusUpdate {
+    Epoch {
+        epoch: Epoch,
+        schedule: Arc<LeaderScheduleRpc>,
+    },
+    Messages(Arc<Vec<GeyserMessage>>),
+}
+
+#[derive(Debug, Default)]
+struct MemoryBusInner {
+    epochs: BTreeMap<Epoch, Arc<LeaderScheduleRpc>>,
+    messages: VecDeque<GeyserMessage>,
+    finalized_slot: Slot,
+}
+
+/// In-process bus, keeps last `maxlen` messages for new consumers
+#[derive(Debug, Clone)]
+pub struct MemoryBus {
+    inner: Arc<Mutex<MemoryBusInner>>,
+    updates_tx: broadcast::Sender<MemoryBusUpdate>,
+    maxlen: usize,
+}
+
+impl MemoryBus {
+    pub fn new(maxlen: usize, channel_capacity: usize) -> Self {
+        let (updates_tx, _updates_rx) = broadcast::channel(channel_capacity);
+        Self {
+            inner: Arc::default(),
+            updates_tx,
+            maxlen,
+        }
+    }
+
+    /// Saved epochs and messages with receiver of new updates
+    pub fn subscribe(&self) -> MemoryBusSubscription {
+        let inner = self.inner.lock().expect("unpoisoned mutex");
+        MemoryBusSubscription {
+            epochs: inner
+                .epochs
+                .iter()
+                .map(|(epoch, schedule)| (*epoch, Arc::clone(schedule)))
+                .collect(),
+            messages: inner.messages.iter().cloned().collect(),
+            updates_rx: self.updates_tx.subscribe(),
+        }
+    }
+
+    pub fn consumer(&self) -> MemoryConsumer {
+        MemoryConsumer {
+            inner: Arc::clone(&self.inner),
+            subscription: self.subscribe(),
+        }
+    }
+}
+
+impl Publisher for MemoryBus {
+    fn get_epochs(&mut self) -> BoxFuture<'_, anyhow::Result<Vec<(Epoch, LeaderScheduleRpc)>>> {
+        let inner = self.inner.lock().expect("unpoisoned mutex");
+        let epochs = inner
+            .epochs
+            .iter()
+            .map(|(epoch, schedule)| (*epoch, schedule.as_ref().clone()))
+            .collect();
+        futures::future::ready(Ok(epochs)).boxed()
+    }
+
+    fn push_epoch(
+        &mut self,
+        epoch: Epoch,
+        schedule: LeaderScheduleRpc,
+    ) -> BoxFuture<'_, anyhow::Result<()>> {
+        let mut inner = self.inner.lock().expect("unpoisoned mutex");
+        let schedule = Arc::new(schedule);
+        inner.epochs.insert(epoch, Arc::clone(&schedule));
+        let _ = self
+            .updates_tx
+            .send(MemoryBusUpdate::Epoch { epoch, schedule });
+        futures::future::ready(Ok(())).boxed()
+    }
+
+    fn push_messages(
+        &mut self,
+        messages: Vec<GeyserMessage>,
+    ) -> BoxFuture<'_, anyhow::Result<Vec<GeyserMessage>>> {
+        let mut inner = self.inner.lock().expect("unpoisoned mutex");
+        for message in messages.iter() {
+            if let GeyserMessage::Status {
+                slot,
+                commitment: CommitmentLevel::Finalized,
+            } = message
+            {
+                inner.finalized_slot = inner.finalized_slot.max(*slot);
+            }
+        }
+
+        let finalized_slot = inner.finalized_slot;
+        let messages = messages
+            .into_iter()
+            .filter(|msg| msg.slot() >= finalized_slot)
+            .collect::<Vec<_>>();
+        if !messages.is_empty() {
+            inner.messages.extend(messages.iter().cloned());
+            while inner.messages.len() > self.maxlen {
+                inner.messages.pop_front();
+            }
+            let _ = self
+                .updates_tx
+                .send(MemoryBusUpdate::Messages(Arc::new(messages.clone())));
+        }
+        futures::future::ready(Ok(messages)).boxed()
+    }
+}
+
+#[derive(Debug)]
+pub struct MemoryBusSubscription {
+    pub epochs: Vec<(Epoch, Arc<LeaderScheduleRpc>)>,
+    pub messages: Vec<GeyserMessage>,
+    pub updates_rx: broadcast::Receiver<MemoryBusUpdate>,
+}
+
+impl MemoryBusSubscription {
+    pub async fn recv(&mut self) -> anyhow::Result<MemoryBusUpdate> {
+        match self.updates_rx.recv().await {
+            Ok(update) => Ok(update),
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                anyhow::bail!("bus consumer lagged by {count} updates")
+            }
+            Err(broadcast::error::RecvError::Closed) => anyhow::bail!("bus is closed"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MemoryConsumer {
+    inner: Arc<Mutex<MemoryBusInner>>,
+    subscription: MemoryBusSubscription,
+}
+
+impl Consumer for MemoryConsumer {
+    fn get_epochs(
+        &mut self,
+        epochs: Option<Vec<Epoch>>,
+    ) -> BoxFuture<'_, anyhow::Result<Vec<(Epoch, LeaderScheduleRpc)>>> {
+        let inner = self.inner.lock().expect("unpoisoned mutex");
+        let epochs = match epochs {
+            Some(epochs) => epochs
+                .into_iter()
+                .filter_map(|epoch| {
+                    inner
+                        .epochs
+                        .get(&epoch)
+                        .map(|schedule| (epoch, schedule.as_ref().clone()))
+                })
+                .collect(),
+            None => inner
+                .epochs
+                .iter()
+                .map(|(epoch, schedule)| (*epoch, schedule.as_ref().clone()))
+                .collect(),
+        };
+        futures::future::ready(Ok(epochs)).boxed()
+    }
+
+    fn read(&mut self) -> BoxFuture<'_, anyhow::Result<Vec<GeyserMessage>>> {
+        async move {
+            if !self.subscription.messages.is_empty() {
+                return Ok(std::mem::take(&mut self.subscription.messages));
+            }
+
+            loop {
+                if let MemoryBusUpdate::Messages(messages) = self.subscription.recv().await? {
+                    return Ok(messages.as_ref().clone());
+                }
+            }
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn create_status(slot: Slot, commitment: CommitmentLevel) -> GeyserMessage {
+        GeyserMessage::Status { slot, commitment }
+    }
+
+    #[tokio::test]
+    async fn publish_consume() {
+        let mut bus = MemoryBus::new(3, 16);
+        let mut consumer = bus.consumer();
+
+        let messages = vec![
+            create_status(10, CommitmentLevel::Processed),
+            create_status(9, CommitmentLevel::Confirmed),
+        ];
+        let pushed = bus.push_messages(messages.clone()).await.expect("pushed");
+        assert_eq!(pushed, messages);
+        assert_eq!(consumer.read().await.expect("read"), messages);
+
+        // messages older than finalized slot are dropped
+        let pushed = bus
+            .push_messages(vec![
+                create_status(8, CommitmentLevel::Finalized),
+                create_status(7, CommitmentLevel::Confirmed),
+                create_status(11, CommitmentLevel::Processed),
+            ])
+            .await
+            .expect("pushed");
+        let expected = vec![
+            create_status(8, CommitmentLevel::Finalized),
+            create_status(11, CommitmentLevel::Processed),
+        ];
+        assert_eq!(pushed, expected);
+        assert_eq!(consumer.read().await.expect("read"), expected);
+
+        // new consumer receives last `maxlen` messages
+        let mut consumer = bus.consumer();
+        assert_eq!(
+            consumer.read().await.expect("read"),
+            vec![
+                create_status(9, CommitmentLevel::Confirmed),
+                create_status(8, CommitmentLevel::Finalized),
+                create_status(11, CommitmentLevel::Processed),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn publish_consume_epochs() {
+        let mut bus = MemoryBus::new(3, 16);
+        let schedule = LeaderScheduleRpc::from([("leader".to_owned(), vec![0, 1, 2, 3])]);
+        bus.push_epoch(5, schedule.clone()).await.expect("pushed");
+
+        let mut consumer = bus.consumer();
+        assert_eq!(
+            consumer.get_epochs(None).await.expect("epochs"),
+            vec![(5, schedule.clone())]
+        );
+        assert_eq!(
+            consumer.get_epochs(Some(vec![4, 5])).await.expect("epochs"),
+            vec![(5, schedule)]
+        );
+    }
+}
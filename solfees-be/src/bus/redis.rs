@@ -0,0 +1,899 @@
+use {
+    crate::{
+        bus::{codec, Consumer, Publisher},
+        config::{
+            ConfigRedisCluster, ConfigRedisConsumer, ConfigRedisConsumerStart,
+            ConfigRedisLeaderElection, ConfigRedisPublisher, ConfigRedisReconnect,
+            ConfigRedisSentinel,
+        },
+        grpc_geyser::{CommitmentLevel, GeyserMessage, SlotSummary},
+        metrics::{grpc2redis as metrics_grpc2redis, solfees_be as metrics},
+        schedule::LeaderScheduleRpc,
+    },
+    anyhow::Context,
+    futures::future::{BoxFuture, FutureExt},
+    redis::{
+        aio::{ConnectionLike, MultiplexedConnection},
+        cluster::ClusterClient,
+        cluster_async::ClusterConnection,
+        sentinel::{SentinelClient, SentinelNodeConnectionInfo, SentinelServerType},
+        streams::{StreamRangeReply, StreamReadReply},
+        AsyncConnectionConfig, Client, Cmd, ConnectionAddr, FromRedisValue, IntoConnectionInfo,
+        Pipeline, RedisError, RedisFuture, RedisResult, TlsMode, Value as RedisValue,
+    },
+    serde::{Deserialize, Serialize},
+    solana_sdk::clock::{Epoch, Slot, UnixTimestamp},
+    std::{
+        collections::BTreeMap,
+        io,
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+    tokio::{
+        fs,
+        sync::{watch, Notify},
+        time::{interval, sleep, MissedTickBehavior},
+    },
+    tracing::{info, warn},
+};
+
+enum RedisConnectionKind {
+    Single(MultiplexedConnection),
+    Cluster(ClusterConnection),
+}
+
+impl ConnectionLike for RedisConnectionKind {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, RedisValue> {
+        match self {
+            Self::Single(connection) => connection.req_packed_command(cmd),
+            Self::Cluster(connection) => connection.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<RedisValue>> {
+        match self {
+            Self::Single(connection) => connection.req_packed_commands(cmd, offset, count),
+            Self::Cluster(connection) => connection.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            Self::Single(connection) => connection.get_db(),
+            Self::Cluster(connection) => connection.get_db(),
+        }
+    }
+}
+
+/// Connection to single node, master discovered with Sentinel or Cluster,
+/// re-created with backoff on connection errors
+struct RedisConnection {
+    endpoint: String,
+    sentinel: Option<ConfigRedisSentinel>,
+    cluster: Option<ConfigRedisCluster>,
+    reconnect: ConfigRedisReconnect,
+    connection: Option<RedisConnectionKind>,
+}
+
+impl RedisConnection {
+    async fn new(
+        endpoint: &str,
+        sentinel: Option<&ConfigRedisSentinel>,
+        cluster: Option<&ConfigRedisCluster>,
+        reconnect: ConfigRedisReconnect,
+        keys: &[&str],
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            sentinel.is_none() || cluster.is_none(),
+            "only one of sentinel or cluster can be used"
+        );
+        // multi-key commands (`MULTI`, `EVAL`) require the same hash slot
+        if cluster.is_some() {
+            let tag = keys.first().copied().map(Self::get_hash_tag);
+            for key in keys {
+                anyhow::ensure!(
+                    Some(Self::get_hash_tag(key)) == tag,
+                    "all keys should have the same hash tag in cluster mode: {keys:?}"
+                );
+            }
+        }
+
+        let mut value = Self {
+            endpoint: endpoint.to_owned(),
+            sentinel: sentinel.cloned(),
+            cluster: cluster.cloned(),
+            reconnect,
+            connection: None,
+        };
+        value.connection = Some(value.connect().await?);
+        Ok(value)
+    }
+
+    fn get_hash_tag(key: &str) -> &str {
+        if let Some(start) = key.find('{') {
+            if let Some(len) = key[start + 1..].find('}') {
+                if len > 0 {
+                    return &key[start + 1..start + 1 + len];
+                }
+            }
+        }
+        key
+    }
+
+    async fn connect(&self) -> anyhow::Result<RedisConnectionKind> {
+        let config = AsyncConnectionConfig::new().set_connection_timeout(Duration::from_secs(2));
+
+        if let Some(cluster) = &self.cluster {
+            let client = ClusterClient::builder(cluster.endpoints.clone())
+                .connection_timeout(Duration::from_secs(2))
+                .build()
+                .context("failed to create Redis Cluster client")?;
+            return client
+                .get_async_connection()
+                .await
+                .map(RedisConnectionKind::Cluster)
+                .context("failed to get Redis Cluster connection");
+        }
+
+        if let Some(sentinel) = &self.sentinel {
+            let info = self
+                .endpoint
+                .as_str()
+                .into_connection_info()
+                .context("failed to parse Redis endpoint")?;
+            let tls_mode = match info.addr {
+                ConnectionAddr::TcpTls { insecure, .. } => Some(if insecure {
+                    TlsMode::Insecure
+                } else {
+                    TlsMode::Secure
+                }),
+                _ => None,
+            };
+            let mut client = SentinelClient::build(
+                sentinel.endpoints.clone(),
+                sentinel.service_name.clone(),
+                Some(SentinelNodeConnectionInfo {
+                    tls_mode,
+                    redis_connection_info: Some(info.redis),
+                }),
+                SentinelServerType::Master,
+            )
+            .context("failed to create Redis Sentinel client")?;
+            return client
+                .get_async_connection_with_config(&config)
+                .await
+                .map(RedisConnectionKind::Single)
+                .context("failed to get Redis connection from Sentinel");
+        }
+
+        let client =
+            Client::open(self.endpoint.as_str()).context("failed to create Redis client")?;
+        client
+            .get_multiplexed_async_connection_with_config(&config)
+            .await
+            .map(RedisConnectionKind::Single)
+            .context("failed to get Redis connection")
+    }
+
+    async fn get(&mut self) -> &mut RedisConnectionKind {
+        if self.connection.is_none() {
+            let mut backoff = self.reconnect.backoff_min;
+            loop {
+                match self.connect().await {
+                    Ok(connection) => {
+                        info!("reconnected to Redis");
+                        self.connection = Some(connection);
+                        break;
+                    }
+                    Err(error) => {
+                        warn!(?error, ?backoff, "failed to reconnect to Redis");
+                        sleep(backoff).await;
+                        backoff = (backoff * 2).min(self.reconnect.backoff_max);
+                    }
+                }
+            }
+        }
+        self.connection.as_mut().expect("connection exists")
+    }
+
+    // connection is dropped and request is repeated on connection errors, other errors returned
+    fn check_error(&mut self, error: RedisError) -> RedisResult<()> {
+        if error.is_io_error()
+            || error.is_connection_dropped()
+            || error.is_connection_refusal()
+            || error.is_timeout()
+            || error.is_unrecoverable_error()
+        {
+            warn!(?error, "Redis connection failed");
+            self.connection = None;
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    async fn query<T: FromRedisValue>(&mut self, cmd: &Cmd) -> RedisResult<T> {
+        loop {
+            match cmd.query_async(self.get().await).await {
+                Ok(value) => return Ok(value),
+                Err(error) => self.check_error(error)?,
+            }
+        }
+    }
+
+    async fn query_pipe<T: FromRedisValue>(&mut self, pipe: &Pipeline) -> RedisResult<T> {
+        loop {
+            match pipe.query_async(self.get().await).await {
+                Ok(value) => return Ok(value),
+                Err(error) => self.check_error(error)?,
+            }
+        }
+    }
+}
+
+async fn get_epochs_all(
+    connection: &mut RedisConnection,
+    epochs_key: &str,
+) -> anyhow::Result<Vec<(Epoch, LeaderScheduleRpc)>> {
+    let epochs = connection
+        .query::<Vec<(Epoch, Vec<u8>)>>(redis::cmd("HGETALL").arg(epochs_key))
+        .await
+        .context("failed to fetch epochs from Redis")?;
+    decode_epochs(epochs)
+}
+
+fn decode_epochs(
+    epochs: impl IntoIterator<Item = (Epoch, Vec<u8>)>,
+) -> anyhow::Result<Vec<(Epoch, LeaderScheduleRpc)>> {
+    epochs
+        .into_iter()
+        .map(|(epoch, data)| {
+            bincode::deserialize(&data)
+                .with_context(|| format!("failed to deserialie epoch {epoch}"))
+                .map(|schedule| (epoch, schedule))
+        })
+        .collect()
+}
+
+/// Summary of finalized slot, member of `history.key` sorted set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedisSlotHistory {
+    pub slot: Slot,
+    pub leader: Option<String>,
+    pub time: UnixTimestamp,
+    pub height: Slot,
+    pub total_transactions: u64,
+    pub total_transactions_vote: u64,
+    pub total_fee: u64,
+    pub total_units_consumed: u64,
+    pub fee_average: f64,
+    pub fee_levels: Vec<u64>, // in order of `history.percentiles`
+}
+
+impl RedisSlotHistory {
+    fn new(summary: &SlotSummary, percentiles: &[u16]) -> Self {
+        let fees = &summary.fees;
+        let fee_average = if fees.is_empty() {
+            0f64
+        } else {
+            fees.iter().map(|fee| *fee as f64).sum::<f64>() / fees.len() as f64
+        };
+        let fee_levels = percentiles
+            .iter()
+            .map(|percentile| {
+                let index = (*percentile as usize).min(9_999) * fees.len() / 10_000;
+                fees.get(index).copied().unwrap_or_default()
+            })
+            .collect();
+
+        Self {
+            slot: summary.slot,
+            leader: summary.leader.map(|leader| leader.to_string()),
+            time: summary.time,
+            height: summary.height,
+            total_transactions: summary.total_transactions,
+            total_transactions_vote: summary.total_transactions_vote,
+            total_fee: summary.total_fee,
+            total_units_consumed: summary.total_units_consumed,
+            fee_average,
+            fee_levels,
+        }
+    }
+}
+
+/// Lock holder is leader until lock is expired, standby tries to acquire lock on every renewal
+struct RedisLeaderElection {
+    config: ConfigRedisLeaderElection,
+    id: String,
+    leader: Arc<AtomicBool>,
+    valid_until: Instant,
+    renewed_at: Option<Instant>,
+}
+
+// unique id of the process
+fn create_instance_id() -> String {
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{}-{ts}", std::process::id())
+}
+
+impl RedisLeaderElection {
+    fn new(config: ConfigRedisLeaderElection) -> Self {
+        Self {
+            config,
+            id: create_instance_id(),
+            leader: Arc::new(AtomicBool::new(false)),
+            valid_until: Instant::now(),
+            renewed_at: None,
+        }
+    }
+
+    async fn is_leader(&mut self, connection: &mut RedisConnection) -> anyhow::Result<bool> {
+        if self
+            .renewed_at
+            .map_or(true, |ts| ts.elapsed() >= self.config.renew_interval)
+        {
+            let ts = Instant::now();
+            let mut cmd = redis::cmd("EVAL");
+            cmd.arg(
+                r#"
+if redis.call("SET", KEYS[1], ARGV[1], "NX", "PX", ARGV[2]) then
+    return 1;
+end
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    redis.call("PEXPIRE", KEYS[1], ARGV[2]);
+    return 1;
+end
+return 0;
+"#,
+            )
+            .arg(1)
+            .arg(&self.config.key)
+            .arg(&self.id)
+            .arg(self.config.ttl.as_millis() as u64);
+            let acquired: bool = connection
+                .query(&cmd)
+                .await
+                .context("failed to acquire leader lock in Redis")?;
+            self.renewed_at = Some(ts);
+            if acquired {
+                self.valid_until = ts + self.config.ttl;
+            }
+        }
+
+        let leader = Instant::now() < self.valid_until;
+        if self.leader.swap(leader, Ordering::Relaxed) != leader {
+            let role = if leader { "active" } else { "standby" };
+            info!(id = %self.id, role, "leader role changed");
+        }
+        metrics_grpc2redis::redis_leader_set(leader);
+        Ok(leader)
+    }
+}
+
+pub struct RedisPublisher {
+    config: ConfigRedisPublisher,
+    connection: RedisConnection,
+    batch_key: String,
+    batch_seq: u64,
+    history_pending: BTreeMap<Slot, RedisSlotHistory>,
+    election: Option<RedisLeaderElection>,
+}
+
+impl RedisPublisher {
+    pub async fn new(config: ConfigRedisPublisher) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            [
+                codec::VERSION_LEGACY,
+                codec::VERSION_PROTOBUF,
+                codec::VERSION_COMPRESSED
+            ]
+            .contains(&config.stream_message_version),
+            "unknown stream_message_version: {}",
+            config.stream_message_version
+        );
+        // per instance, result of another instance batch is never read
+        let batch_key = format!("{}:{}", config.batch_key, create_instance_id());
+        let mut keys = vec![
+            config.slot_finalized.as_str(),
+            batch_key.as_str(),
+            config.stream_key.as_str(),
+            config.epochs_key.as_str(),
+        ];
+        if let Some(history) = &config.history {
+            anyhow::ensure!(
+                history
+                    .percentiles
+                    .iter()
+                    .all(|percentile| *percentile <= 10_000),
+                "history percentiles should be in range 0..=10000"
+            );
+            keys.push(history.key.as_str());
+        }
+        if let Some(election) = &config.leader_election {
+            anyhow::ensure!(
+                election.renew_interval < election.ttl,
+                "leader_election renew_interval should be less than ttl"
+            );
+            keys.push(election.key.as_str());
+        }
+        let connection = RedisConnection::new(
+            &config.endpoint,
+            config.sentinel.as_ref(),
+            config.cluster.as_ref(),
+            config.reconnect,
+            &keys,
+        )
+        .await?;
+        let election = config.leader_election.clone().map(RedisLeaderElection::new);
+        metrics_grpc2redis::redis_leader_set(election.is_none());
+        Ok(Self {
+            config,
+            connection,
+            batch_key,
+            batch_seq: 0,
+            history_pending: BTreeMap::new(),
+            election,
+        })
+    }
+
+    /// Shared flag of active role, `None` without leader election
+    pub fn get_leader(&self) -> Option<Arc<AtomicBool>> {
+        self.election
+            .as_ref()
+            .map(|election| Arc::clone(&election.leader))
+    }
+
+    // summaries are kept until slot is finalized, skipped slots are dropped
+    fn get_history_finalized(
+        pending: &mut BTreeMap<Slot, RedisSlotHistory>,
+        percentiles: &[u16],
+        messages: &[GeyserMessage],
+    ) -> Vec<RedisSlotHistory> {
+        let mut finalized = vec![];
+        for message in messages {
+            match message {
+                GeyserMessage::Status {
+                    slot,
+                    commitment: CommitmentLevel::Finalized,
+                } => {
+                    if let Some(summary) = pending.remove(slot) {
+                        finalized.push(summary);
+                    }
+                    *pending = pending.split_off(slot);
+                }
+                GeyserMessage::Status { .. } => {}
+                GeyserMessage::Slot { .. } => {
+                    if let GeyserMessage::SlotSummary(summary) = message.clone().into_summary() {
+                        let value = RedisSlotHistory::new(&summary, percentiles);
+                        pending.insert(summary.slot, value);
+                    }
+                }
+                GeyserMessage::SlotSummary(summary) => {
+                    let value = RedisSlotHistory::new(summary, percentiles);
+                    pending.insert(summary.slot, value);
+                }
+            }
+        }
+        finalized
+    }
+}
+
+impl Publisher for RedisPublisher {
+    fn get_epochs(&mut self) -> BoxFuture<'_, anyhow::Result<Vec<(Epoch, LeaderScheduleRpc)>>> {
+        get_epochs_all(&mut self.connection, &self.config.epochs_key).boxed()
+    }
+
+    fn push_epoch(
+        &mut self,
+        epoch: Epoch,
+        schedule: LeaderScheduleRpc,
+    ) -> BoxFuture<'_, anyhow::Result<()>> {
+        async move {
+            let mut pipe = redis::pipe();
+            pipe.cmd("HSET")
+                .arg(&self.config.epochs_key)
+                .arg(epoch)
+                .arg(bincode::serialize(&schedule).context("failed to serialize leader schedule")?)
+                .ignore()
+                .atomic();
+            self.connection
+                .query_pipe(&pipe)
+                .await
+                .context("failed to send epoch schedule to Redis")
+        }
+        .boxed()
+    }
+
+    fn push_messages(
+        &mut self,
+        messages: Vec<GeyserMessage>,
+    ) -> BoxFuture<'_, anyhow::Result<Vec<GeyserMessage>>> {
+        async move {
+            if let Some(election) = &mut self.election {
+                if !election.is_leader(&mut self.connection).await? {
+                    // keep pending summaries for takeover
+                    if let Some(history) = &self.config.history {
+                        Self::get_history_finalized(
+                            &mut self.history_pending,
+                            &history.percentiles,
+                            &messages,
+                        );
+                    }
+                    return Ok(vec![]);
+                }
+            }
+
+            let finalized_slot = messages
+                .iter()
+                .filter_map(|message| {
+                    if let GeyserMessage::Status {
+                        slot,
+                        commitment: CommitmentLevel::Finalized,
+                    } = message
+                    {
+                        Some(*slot)
+                    } else {
+                        None
+                    }
+                })
+                .max()
+                .unwrap_or_default();
+
+            let mut pipe = redis::pipe();
+            if let Some(history) = &self.config.history {
+                let finalized = Self::get_history_finalized(
+                    &mut self.history_pending,
+                    &history.percentiles,
+                    &messages,
+                );
+                for summary in finalized.iter() {
+                    // replace summary pushed by another instance
+                    pipe.cmd("ZREMRANGEBYSCORE")
+                        .arg(&history.key)
+                        .arg(summary.slot)
+                        .arg(summary.slot)
+                        .ignore();
+                    pipe.cmd("ZADD")
+                        .arg(&history.key)
+                        .arg(summary.slot)
+                        .arg(
+                            serde_json::to_string(summary)
+                                .context("failed to serialize slot summary")?,
+                        )
+                        .ignore();
+                }
+                if let Some(summary) = finalized.last() {
+                    pipe.cmd("ZREMRANGEBYSCORE")
+                        .arg(&history.key)
+                        .arg("-inf")
+                        .arg(format!(
+                            "({}",
+                            summary.slot.saturating_sub(history.retention_slots)
+                        ))
+                        .ignore();
+                }
+            }
+
+            // finalized slot and messages are checked and added atomically, returns
+            // finalized slot and flag of added for every message; result is saved with
+            // batch seq, so retry after connection error returns it instead of adding twice;
+            // only the last batch of this instance is kept, older batches are not deduplicated
+            self.batch_seq += 1;
+            let cmd = pipe.cmd("EVAL");
+            cmd.arg(
+                r#"
+local current = tonumber(redis.call("GET", KEYS[1])) or 0;
+local added = {};
+local prefix = ARGV[4] .. ":";
+local last = redis.call("GET", KEYS[2]);
+if last and string.sub(last, 1, #prefix) == prefix then
+    for i = #prefix + 1, #last do
+        table.insert(added, tonumber(string.sub(last, i, i)));
+    end
+    return {current, added};
+end
+local new = tonumber(ARGV[3]);
+if current < new then
+    redis.call("SET", KEYS[1], ARGV[3]);
+    current = new;
+end
+for i = 5, #ARGV, 2 do
+    if tonumber(ARGV[i]) >= current then
+        redis.call("XADD", KEYS[3], "MAXLEN", "~", ARGV[1], "*", ARGV[2], ARGV[i + 1]);
+        table.insert(added, 1);
+    else
+        table.insert(added, 0);
+    end
+end
+redis.call("SET", KEYS[2], prefix .. table.concat(added), "PX", 60000);
+return {current, added};
+"#,
+            )
+            .arg(3)
+            .arg(&self.config.slot_finalized)
+            .arg(&self.batch_key)
+            .arg(&self.config.stream_key)
+            .arg(self.config.stream_maxlen)
+            .arg(&self.config.stream_field_key)
+            .arg(finalized_slot)
+            .arg(self.batch_seq);
+            for message in messages.iter() {
+                cmd.arg(message.slot()).arg(codec::encode(
+                    message,
+                    self.config.stream_message_version,
+                    self.config.stream_compression,
+                )?);
+            }
+
+            let ((_finalized_slot, added),): ((Slot, Vec<bool>),) = self
+                .connection
+                .query_pipe(pipe.atomic())
+                .await
+                .context("failed to send data to Redis stream")?;
+            anyhow::ensure!(
+                added.len() == messages.len(),
+                "invalid number of flags from Redis script"
+            );
+
+            metrics_grpc2redis::redis_messages_dropped_inc_by(
+                added.iter().filter(|added| !**added).count(),
+            );
+            let messages = messages
+                .into_iter()
+                .zip(added)
+                .filter_map(|(message, added)| added.then_some(message))
+                .collect::<Vec<_>>();
+
+            Ok(messages)
+        }
+        .boxed()
+    }
+}
+
+pub struct RedisConsumer {
+    config: ConfigRedisConsumer,
+    connection: RedisConnection,
+    latest_id: String,
+    read_count: usize,
+}
+
+impl RedisConsumer {
+    pub async fn new(config: ConfigRedisConsumer) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            config.read_count_min > 0 && config.read_count_min <= config.read_count_max,
+            "invalid read_count_min / read_count_max"
+        );
+        let mut connection = RedisConnection::new(
+            &config.endpoint,
+            config.sentinel.as_ref(),
+            config.cluster.as_ref(),
+            config.reconnect,
+            &[config.stream_key.as_str(), config.epochs_key.as_str()],
+        )
+        .await?;
+        let latest_id = Self::get_start_id(&config, &mut connection).await?;
+        info!(id = %latest_id, "start reading Redis stream");
+        Ok(Self {
+            read_count: config.read_count_min,
+            config,
+            connection,
+            latest_id,
+        })
+    }
+
+    /// Saver of applied stream IDs, `None` if `last_id_path` is not set
+    pub fn last_id_saver(&self) -> Option<RedisLastIdSaver> {
+        self.config
+            .last_id_path
+            .clone()
+            .map(|path| RedisLastIdSaver {
+                path,
+                interval: self.config.last_id_save_interval,
+            })
+    }
+
+    async fn get_start_id(
+        config: &ConfigRedisConsumer,
+        connection: &mut RedisConnection,
+    ) -> anyhow::Result<String> {
+        if let Some(path) = &config.last_id_path {
+            match fs::read_to_string(path).await {
+                Ok(id) if !id.trim().is_empty() => return Ok(id.trim().to_owned()),
+                Ok(_) => {}
+                Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+                Err(error) => {
+                    return Err(error).with_context(|| format!("failed to read {path:?}"));
+                }
+            }
+        }
+
+        Ok(match &config.start {
+            ConfigRedisConsumerStart::Begin => "0".to_owned(),
+            // resolve `$` once, otherwise messages between `XREAD` calls can be lost
+            ConfigRedisConsumerStart::End => {
+                let StreamRangeReply { ids } = connection
+                    .query(
+                        redis::cmd("XREVRANGE")
+                            .arg(&config.stream_key)
+                            .arg("+")
+                            .arg("-")
+                            .arg("COUNT")
+                            .arg(1),
+                    )
+                    .await
+                    .context("failed to get last ID of Redis stream")?;
+                ids.into_iter()
+                    .next()
+                    .map(|stream_id| stream_id.id)
+                    .unwrap_or_else(|| "0".to_owned())
+            }
+            ConfigRedisConsumerStart::Id(id) => id.clone(),
+            // stream IDs are `<unix time ms>-<seq>`
+            ConfigRedisConsumerStart::Ago(duration) => {
+                let ts = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .context("invalid system time")?
+                    .saturating_sub(*duration);
+                format!("{}-0", ts.as_millis())
+            }
+        })
+    }
+}
+
+impl Consumer for RedisConsumer {
+    fn get_epochs(
+        &mut self,
+        epochs: Option<Vec<Epoch>>,
+    ) -> BoxFuture<'_, anyhow::Result<Vec<(Epoch, LeaderScheduleRpc)>>> {
+        async move {
+            let Some(epochs) = epochs else {
+                return get_epochs_all(&mut self.connection, &self.config.epochs_key).await;
+            };
+
+            let value = self
+                .connection
+                .query::<Vec<Option<Vec<u8>>>>(
+                    redis::cmd("HMGET")
+                        .arg(&self.config.epochs_key)
+                        .arg(&epochs),
+                )
+                .await
+                .context("failed to fetch epochs from Redis")?;
+            decode_epochs(
+                epochs
+                    .into_iter()
+                    .zip(value)
+                    .filter_map(|(epoch, maybe_data)| maybe_data.map(|data| (epoch, data))),
+            )
+        }
+        .boxed()
+    }
+
+    fn read(&mut self) -> BoxFuture<'_, anyhow::Result<Vec<GeyserMessage>>> {
+        async move {
+            loop {
+                let ts = Instant::now();
+                let StreamReadReply {
+                    keys: mut stream_keys,
+                } = self
+                    .connection
+                    .query(
+                        redis::cmd("XREAD")
+                            .arg("COUNT")
+                            .arg(self.read_count)
+                            .arg("BLOCK")
+                            .arg(self.config.read_block.as_millis() as u64)
+                            .arg("STREAMS")
+                            .arg(&self.config.stream_key)
+                            .arg(&self.latest_id),
+                    )
+                    .await
+                    .context("failed to read Redis stream")?;
+
+                let Some(stream_key) = stream_keys.pop() else {
+                    continue;
+                };
+                metrics::redis_read_observe(ts.elapsed(), stream_key.ids.len());
+
+                // double `COUNT` if we hit the limit, halve if batch is less than half
+                if stream_key.ids.len() >= self.read_count {
+                    self.read_count = (self.read_count * 2).min(self.config.read_count_max);
+                } else if stream_key.ids.len() < self.read_count / 2 {
+                    self.read_count = (self.read_count / 2).max(self.config.read_count_min);
+                }
+
+                let mut messages = Vec::with_capacity(stream_key.ids.len());
+                for stream_id in stream_key.ids {
+                    self.latest_id = stream_id.id;
+
+                    let Some(RedisValue::BulkString(payload)) =
+                        stream_id.map.get(&self.config.stream_field_key)
+                    else {
+                        metrics::bus_decode_failed_inc();
+                        warn!(id = %self.latest_id, "failed to get payload from StreamId");
+                        continue;
+                    };
+
+                    match codec::decode(payload) {
+                        Ok(message) => messages.push(message),
+                        Err(error) => {
+                            metrics::bus_decode_failed_inc();
+                            warn!(id = %self.latest_id, ?error, "failed to decode payload");
+                        }
+                    }
+                }
+                if !messages.is_empty() {
+                    return Ok(messages);
+                }
+            }
+        }
+        .boxed()
+    }
+
+    fn position(&self) -> Option<String> {
+        Some(self.latest_id.clone())
+    }
+}
+
+/// Saves ID of the last applied stream message to `last_id_path`, so restart does not skip
+/// messages which were read but not applied
+#[derive(Debug)]
+pub struct RedisLastIdSaver {
+    path: PathBuf,
+    interval: Duration,
+}
+
+impl RedisLastIdSaver {
+    /// Save on every `interval` if ID is changed and once more on shutdown
+    pub async fn run(
+        self,
+        mut position_rx: watch::Receiver<Option<String>>,
+        shutdown: Arc<Notify>,
+    ) -> anyhow::Result<()> {
+        let mut saved = None;
+        let mut timer = interval(self.interval);
+        timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            let is_shutdown = tokio::select! {
+                _ = shutdown.notified() => true,
+                _ = timer.tick() => false,
+            };
+
+            let position = position_rx.borrow_and_update().clone();
+            if let Some(position) = position.filter(|position| saved.as_ref() != Some(position)) {
+                self.save(&position).await;
+                saved = Some(position);
+            }
+
+            if is_shutdown {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn save(&self, id: &str) {
+        // write to temporary file first, so restart never see partially written ID
+        let path_tmp = self.path.with_extension("tmp");
+        if let Err(error) = async {
+            fs::write(&path_tmp, id).await?;
+            fs::rename(&path_tmp, &self.path).await
+        }
+        .await
+        {
+            warn!(?error, path = ?self.path, "failed to save last Redis stream ID");
+        }
+    }
+}
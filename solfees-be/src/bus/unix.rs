@@ -0,0 +1,184 @@
+use {
+    crate::{
+        bus::{
+            memory::{MemoryBus, MemoryBusSubscription, MemoryBusUpdate},
+            Consumer,
+        },
+        config::ConfigBusUnixConsumer,
+        grpc_geyser::GeyserMessage,
+        schedule::LeaderScheduleRpc,
+    },
+    anyhow::Context,
+    futures::future::{BoxFuture, FutureExt},
+    serde::{Deserialize, Serialize},
+    solana_sdk::clock::Epoch,
+    std::{borrow::Cow, collections::HashMap, io, path::Path},
+    tokio::{
+        fs,
+        io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter},
+        net::{UnixListener, UnixStream},
+    },
+    tracing::{error, info, warn},
+};
+
+const MAX_FRAME_SIZE: usize = 256 * 1024 * 1024; // 256MiB
+
+// every frame is `u32` length (little endian) followed by bincode encoded `UnixFrame`
+#[derive(Debug, Serialize, Deserialize)]
+enum UnixFrame<'a> {
+    Epoch {
+        epoch: Epoch,
+        schedule: Cow<'a, LeaderScheduleRpc>,
+    },
+    Messages(Cow<'a, [GeyserMessage]>),
+}
+
+async fn write_frame(
+    stream: &mut BufWriter<UnixStream>,
+    frame: &UnixFrame<'_>,
+) -> anyhow::Result<()> {
+    let data = bincode::serialize(frame).context("failed to serialize frame")?;
+    stream.write_u32_le(data.len() as u32).await?;
+    stream.write_all(&data).await?;
+    Ok(())
+}
+
+async fn read_frame(stream: &mut BufReader<UnixStream>) -> anyhow::Result<UnixFrame<'static>> {
+    let size = stream.read_u32_le().await? as usize;
+    anyhow::ensure!(size <= MAX_FRAME_SIZE, "frame is too large: {size} bytes");
+    let mut data = vec![0; size];
+    stream.read_exact(&mut data).await?;
+    bincode::deserialize(&data).context("failed to decode frame")
+}
+
+/// Serve messages pushed to the `bus` on Unix socket
+pub async fn listen(path: &Path, bus: MemoryBus) -> anyhow::Result<()> {
+    match fs::remove_file(path).await {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+        Err(error) => {
+            return Err(error).with_context(|| format!("failed to remove {path:?}"));
+        }
+    }
+    let listener =
+        UnixListener::bind(path).with_context(|| format!("failed to bind Unix socket {path:?}"))?;
+    info!(?path, "start bus server");
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _addr)) => {
+                    let subscription = bus.subscribe();
+                    tokio::spawn(async move {
+                        if let Err(error) = send_updates(stream, subscription).await {
+                            warn!(?error, "bus consumer disconnected");
+                        }
+                    });
+                }
+                Err(error) => {
+                    error!(?error, "failed to accept bus consumer");
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+async fn send_updates(
+    stream: UnixStream,
+    mut subscription: MemoryBusSubscription,
+) -> anyhow::Result<()> {
+    let mut stream = BufWriter::new(stream);
+
+    for (epoch, schedule) in subscription.epochs.iter() {
+        let schedule = Cow::Borrowed(schedule.as_ref());
+        write_frame(
+            &mut stream,
+            &UnixFrame::Epoch {
+                epoch: *epoch,
+                schedule,
+            },
+        )
+        .await?;
+    }
+    if !subscription.messages.is_empty() {
+        let messages = Cow::Borrowed(subscription.messages.as_slice());
+        write_frame(&mut stream, &UnixFrame::Messages(messages)).await?;
+    }
+    stream.flush().await?;
+
+    loop {
+        match subscription.recv().await? {
+            MemoryBusUpdate::Epoch { epoch, schedule } => {
+                let schedule = Cow::Borrowed(schedule.as_ref());
+                write_frame(&mut stream, &UnixFrame::Epoch { epoch, schedule }).await?;
+            }
+            MemoryBusUpdate::Messages(messages) => {
+                let messages = Cow::Borrowed(messages.as_slice());
+                write_frame(&mut stream, &UnixFrame::Messages(messages)).await?;
+            }
+        }
+        stream.flush().await?;
+    }
+}
+
+#[derive(Debug)]
+pub struct UnixConsumer {
+    stream: BufReader<UnixStream>,
+    epochs: HashMap<Epoch, LeaderScheduleRpc>,
+}
+
+impl UnixConsumer {
+    pub async fn new(config: ConfigBusUnixConsumer) -> anyhow::Result<Self> {
+        let stream = UnixStream::connect(&config.path)
+            .await
+            .with_context(|| format!("failed to connect to Unix socket {:?}", config.path))?;
+        Ok(Self {
+            stream: BufReader::new(stream),
+            epochs: HashMap::new(),
+        })
+    }
+}
+
+impl Consumer for UnixConsumer {
+    // epochs are pushed by the server before messages, so we keep received schedules
+    fn get_epochs(
+        &mut self,
+        epochs: Option<Vec<Epoch>>,
+    ) -> BoxFuture<'_, anyhow::Result<Vec<(Epoch, LeaderScheduleRpc)>>> {
+        let epochs = match epochs {
+            Some(epochs) => epochs
+                .into_iter()
+                .filter_map(|epoch| {
+                    self.epochs
+                        .get(&epoch)
+                        .map(|schedule| (epoch, schedule.clone()))
+                })
+                .collect(),
+            None => self
+                .epochs
+                .iter()
+                .map(|(epoch, schedule)| (*epoch, schedule.clone()))
+                .collect(),
+        };
+        futures::future::ready(Ok(epochs)).boxed()
+    }
+
+    fn read(&mut self) -> BoxFuture<'_, anyhow::Result<Vec<GeyserMessage>>> {
+        async move {
+            loop {
+                match read_frame(&mut self.stream).await? {
+                    UnixFrame::Epoch { epoch, schedule } => {
+                        self.epochs.insert(epoch, schedule.into_owned());
+                    }
+                    UnixFrame::Messages(messages) if !messages.is_empty() => {
+                        return Ok(messages.into_owned());
+                    }
+                    UnixFrame::Messages(_) => {}
+                }
+            }
+        }
+        .boxed()
+    }
+}
@@ -5,8 +5,10 @@ use {
         Deserialize,
     },
     std::{
+        collections::HashMap,
         fmt,
         net::{IpAddr, Ipv4Addr, SocketAddr},
+        path::PathBuf,
         str::FromStr,
         time::Duration,
     },
@@ -22,7 +24,11 @@ pub struct ConfigGrpc2Redis {
     pub tracing: ConfigTracing,
     pub rpc: ConfigRpc,
     pub grpc: ConfigGrpc,
+    /// Push aggregated fees instead of transactions of the slot
+    pub slot_summary: bool,
+    pub bus: ConfigBusKind,
     pub redis: ConfigRedisPublisher,
+    pub unix: ConfigBusUnixPublisher,
     pub listen_admin: ConfigListenAdmin,
 }
 
@@ -77,26 +83,191 @@ impl Default for ConfigGrpc {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigBusKind {
+    #[default]
+    Redis,
+    Unix,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigBusCompression {
+    #[default]
+    None,
+    Zstd,
+    Lz4,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ConfigBusMemory {
+    /// Number of messages kept for new consumers
+    pub maxlen: usize,
+    pub channel_capacity: usize,
+}
+
+impl Default for ConfigBusMemory {
+    fn default() -> Self {
+        Self {
+            maxlen: 60 * 3 * 4, // ~1min (2.5 slots per sec, 4 events per slot)
+            channel_capacity: 1_024,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ConfigBusUnixPublisher {
+    pub path: PathBuf,
+    pub memory: ConfigBusMemory,
+}
+
+impl Default for ConfigBusUnixPublisher {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("/tmp/solfees-bus.sock"),
+            memory: ConfigBusMemory::default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ConfigBusUnixConsumer {
+    pub path: PathBuf,
+}
+
+impl Default for ConfigBusUnixConsumer {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("/tmp/solfees-bus.sock"),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields, default)]
 pub struct ConfigRedisPublisher {
     pub endpoint: String,
+    /// Redis Sentinel, `endpoint` is used only for database and credentials
+    pub sentinel: Option<ConfigRedisSentinel>,
+    /// Redis Cluster, all keys should have the same hash tag: `{solfees}:events`
+    pub cluster: Option<ConfigRedisCluster>,
+    pub reconnect: ConfigRedisReconnect,
     pub slot_finalized: String,
+    /// Prefix of key with result of the last pushed batch (suffixed by instance id),
+    /// retry of the batch after connection error is not added twice
+    pub batch_key: String,
     pub stream_key: String,
     pub stream_maxlen: u64,
     pub stream_field_key: String,
+    /// Format of messages, see `proto/bus.proto`; `1` (legacy) by default,
+    /// `2` / `3` should be set only after all consumers are updated
+    pub stream_message_version: u8,
+    /// Compression of messages, only with `stream_message_version: 3`
+    pub stream_compression: ConfigBusCompression,
     pub epochs_key: String,
+    /// Summaries of finalized slots, kept longer than the stream
+    pub history: Option<ConfigRedisHistory>,
+    /// Only one instance with the same lock pushes messages, others are in standby
+    pub leader_election: Option<ConfigRedisLeaderElection>,
 }
 
 impl Default for ConfigRedisPublisher {
     fn default() -> Self {
         Self {
             endpoint: "redis://127.0.0.1:6379/".to_owned(),
+            sentinel: None,
+            cluster: None,
+            reconnect: ConfigRedisReconnect::default(),
             slot_finalized: "solfees:finalized".to_owned(),
+            batch_key: "solfees:batch".to_owned(),
             stream_key: "solfees:events".to_owned(),
             stream_maxlen: 15 * 60 * 3 * 4, // ~15min (2.5 slots per sec, 4 events per slot)
             stream_field_key: "message".to_owned(),
+            stream_message_version: 1,
+            stream_compression: ConfigBusCompression::default(),
             epochs_key: "solfees:epochs".to_owned(),
+            history: None,
+            leader_election: None,
+        }
+    }
+}
+
+/// Sorted set with JSON summary of finalized slot as member and slot as score
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ConfigRedisHistory {
+    pub key: String,
+    /// Summaries older than `finalized - retention_slots` are removed
+    pub retention_slots: u64,
+    /// Fee percentiles in basis points (`0..=10_000`)
+    pub percentiles: Vec<u16>,
+}
+
+impl Default for ConfigRedisHistory {
+    fn default() -> Self {
+        Self {
+            key: "solfees:history".to_owned(),
+            retention_slots: 7 * 24 * 60 * 60 * 5 / 2, // ~7 days (2.5 slots per sec)
+            percentiles: vec![2_500, 5_000, 7_500, 9_000, 9_500, 10_000],
+        }
+    }
+}
+
+/// Lock acquired with `SET NX PX` and renewed by the holder
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ConfigRedisLeaderElection {
+    pub key: String,
+    /// Lock is lost if not renewed within this time
+    #[serde(with = "humantime_serde")]
+    pub ttl: Duration,
+    /// Renewal by leader and acquire attempts by standby
+    #[serde(with = "humantime_serde")]
+    pub renew_interval: Duration,
+}
+
+impl Default for ConfigRedisLeaderElection {
+    fn default() -> Self {
+        Self {
+            key: "solfees:leader".to_owned(),
+            ttl: Duration::from_secs(3),
+            renew_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigRedisSentinel {
+    pub endpoints: Vec<String>,
+    pub service_name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigRedisCluster {
+    pub endpoints: Vec<String>,
+}
+
+/// Exponential backoff between reconnect attempts
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ConfigRedisReconnect {
+    #[serde(with = "humantime_serde")]
+    pub backoff_min: Duration,
+    #[serde(with = "humantime_serde")]
+    pub backoff_max: Duration,
+}
+
+impl Default for ConfigRedisReconnect {
+    fn default() -> Self {
+        Self {
+            backoff_min: Duration::from_millis(100),
+            backoff_max: Duration::from_secs(10),
         }
     }
 }
@@ -106,12 +277,14 @@ impl Default for ConfigRedisPublisher {
 pub struct ConfigListenAdmin {
     #[serde(deserialize_with = "deserialize_maybe_env")]
     pub bind: SocketAddr,
+    pub tls: Option<ConfigTls>,
 }
 
 impl Default for ConfigListenAdmin {
     fn default() -> Self {
         Self {
             bind: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8000),
+            tls: None,
         }
     }
 }
@@ -120,10 +293,15 @@ impl Default for ConfigListenAdmin {
 #[serde(deny_unknown_fields, default)]
 pub struct ConfigBe {
     pub tracing: ConfigTracing,
+    pub bus: ConfigBusKind,
     pub redis: ConfigRedisConsumer,
+    pub unix: ConfigBusUnixConsumer,
     pub listen_admin: ConfigListenAdmin,
     pub listen_rpc: ConfigListenRpc,
+    pub listen_grpc: Option<ConfigListenGrpc>,
     pub metrics: ConfigMetrics,
+    pub limits: ConfigLimits,
+    pub auth: ConfigAuth,
 }
 
 impl WithConfigTracing for ConfigBe {
@@ -132,26 +310,88 @@ impl WithConfigTracing for ConfigBe {
     }
 }
 
+/// `solfees-grpc2redis` and `solfees-be` in one process connected with in-memory bus
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ConfigAllInOne {
+    pub tracing: ConfigTracing,
+    pub rpc: ConfigRpc,
+    pub grpc: ConfigGrpc,
+    pub memory: ConfigBusMemory,
+    pub listen_admin: ConfigListenAdmin,
+    pub listen_rpc: ConfigListenRpc,
+    pub listen_grpc: Option<ConfigListenGrpc>,
+    pub metrics: ConfigMetrics,
+    pub limits: ConfigLimits,
+    pub auth: ConfigAuth,
+}
+
+impl WithConfigTracing for ConfigAllInOne {
+    fn get_tracing(&self) -> &ConfigTracing {
+        &self.tracing
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields, default)]
 pub struct ConfigRedisConsumer {
     pub endpoint: String,
+    /// Redis Sentinel, `endpoint` is used only for database and credentials
+    pub sentinel: Option<ConfigRedisSentinel>,
+    /// Redis Cluster, all keys should have the same hash tag: `{solfees}:events`
+    pub cluster: Option<ConfigRedisCluster>,
+    pub reconnect: ConfigRedisReconnect,
     pub stream_key: String,
     pub stream_field_key: String,
     pub epochs_key: String,
+    /// `BLOCK` timeout of `XREAD`
+    #[serde(with = "humantime_serde")]
+    pub read_block: Duration,
+    /// `COUNT` of `XREAD` is adjusted between min and max by size of received batches
+    pub read_count_min: usize,
+    pub read_count_max: usize,
+    /// Stream position used on start if saved position is not available
+    pub start: ConfigRedisConsumerStart,
+    /// File where ID of the last applied stream message is saved, `None` disables saving
+    pub last_id_path: Option<PathBuf>,
+    #[serde(with = "humantime_serde")]
+    pub last_id_save_interval: Duration,
 }
 
 impl Default for ConfigRedisConsumer {
     fn default() -> Self {
         Self {
             endpoint: "redis://127.0.0.1:6379/".to_owned(),
+            sentinel: None,
+            cluster: None,
+            reconnect: ConfigRedisReconnect::default(),
             stream_key: "solfees:events".to_owned(),
             stream_field_key: "message".to_owned(),
             epochs_key: "solfees:epochs".to_owned(),
+            read_block: Duration::from_secs(1),
+            read_count_min: 5,
+            read_count_max: 500,
+            start: ConfigRedisConsumerStart::default(),
+            last_id_path: None,
+            last_id_save_interval: Duration::from_secs(1),
         }
     }
 }
 
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "lowercase")]
+pub enum ConfigRedisConsumerStart {
+    /// Whole stream, `0`
+    #[default]
+    Begin,
+    /// Only new messages, `$`
+    End,
+    /// Messages after stream ID
+    Id(String),
+    /// Messages added in the last period, ID is built from current time
+    Ago(#[serde(with = "humantime_serde")] Duration),
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields, default)]
 pub struct ConfigListenRpc {
@@ -160,11 +400,17 @@ pub struct ConfigListenRpc {
     #[serde(deserialize_with = "deserialize_humansize")]
     pub body_limit: usize,
     pub request_calls_max: usize,
+    /// Weight of the method in `request_calls_max`, default weight is `1`
+    pub request_calls_weights: HashMap<String, usize>,
     #[serde(with = "humantime_serde")]
     pub request_timeout: Duration,
     pub calls_queue_max: usize,
     pub streams_channel_capacity: usize,
     pub pool_size: usize,
+    #[serde(deserialize_with = "deserialize_humansize")]
+    pub compression_threshold: usize,
+    pub tls: Option<ConfigTls>,
+    pub cors: ConfigCors,
 }
 
 impl Default for ConfigListenRpc {
@@ -173,14 +419,78 @@ impl Default for ConfigListenRpc {
             bind: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8000),
             body_limit: 16 * 1024, // 16KiB
             request_calls_max: 5,
+            request_calls_weights: HashMap::new(),
             request_timeout: Duration::from_secs(60),
             calls_queue_max: 16_384,
             streams_channel_capacity: 512,
             pool_size: 2,
+            compression_threshold: 1024, // 1KiB
+            tls: None,
+            cors: ConfigCors::default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ConfigListenGrpc {
+    #[serde(deserialize_with = "deserialize_maybe_env")]
+    pub bind: SocketAddr,
+    pub tls: Option<ConfigTls>,
+}
+
+impl Default for ConfigListenGrpc {
+    fn default() -> Self {
+        Self {
+            bind: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8002),
+            tls: None,
         }
     }
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ConfigCors {
+    /// `*` allows any origin
+    pub allowed_origins: Vec<String>,
+    /// Client id and API key headers are always allowed
+    pub allowed_headers: Vec<String>,
+    #[serde(with = "humantime_serde")]
+    pub max_age: Duration,
+}
+
+impl Default for ConfigCors {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["*".to_owned()],
+            allowed_headers: vec!["content-type".to_owned()],
+            max_age: Duration::from_secs(86_400),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigTls {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    /// Require client certificates signed by CA from this file
+    #[serde(default)]
+    pub client_ca: Option<PathBuf>,
+    /// Interval for checking modification time of files
+    #[serde(
+        default = "ConfigTls::default_reload_interval",
+        with = "humantime_serde"
+    )]
+    pub reload_interval: Duration,
+}
+
+impl ConfigTls {
+    const fn default_reload_interval() -> Duration {
+        Duration::from_secs(10)
+    }
+}
+
 #[derive(Debug, Default, Deserialize)]
 #[serde(deny_unknown_fields, default)]
 pub struct ConfigMetrics {
@@ -188,6 +498,48 @@ pub struct ConfigMetrics {
     pub usage_subscription_id: Option<String>,
 }
 
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ConfigLimits {
+    pub requests: Option<ConfigLimitsRate>,
+    pub calls: Option<ConfigLimitsRate>,
+    pub batch_calls: Option<usize>,
+    pub egress: Option<ConfigLimitsRate>,
+    pub websockets: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigLimitsRate {
+    pub per_second: u64,
+    pub burst: u64,
+}
+
+impl ConfigLimitsRate {
+    pub const ZERO: Self = Self {
+        per_second: 0,
+        burst: 0,
+    };
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ConfigAuth {
+    pub keys_file: Option<PathBuf>,
+    pub header: String,
+    pub query: String,
+}
+
+impl Default for ConfigAuth {
+    fn default() -> Self {
+        Self {
+            keys_file: None,
+            header: "x-api-key".to_owned(),
+            query: "api-key".to_owned(),
+        }
+    }
+}
+
 fn deserialize_maybe_env<'de, T, D>(deserializer: D) -> Result<T, D::Error>
 where
     T: serde::de::DeserializeOwned + FromStr,
@@ -0,0 +1,76 @@
+use {
+    crate::config::ConfigCors,
+    hyper::header::{
+        HeaderMap, HeaderValue, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+        ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE, ORIGIN,
+        VARY,
+    },
+};
+
+#[derive(Debug)]
+pub struct Cors {
+    any_origin: bool,
+    allowed_origins: Vec<String>,
+    allowed_headers: HeaderValue,
+    max_age: HeaderValue,
+}
+
+impl Cors {
+    pub fn new<'a>(
+        config: ConfigCors,
+        extra_headers: impl IntoIterator<Item = &'a str>,
+    ) -> anyhow::Result<Self> {
+        let mut allowed_headers = config
+            .allowed_headers
+            .iter()
+            .map(|header| header.to_ascii_lowercase())
+            .chain(extra_headers.into_iter().map(str::to_ascii_lowercase))
+            .collect::<Vec<_>>();
+        allowed_headers.sort_unstable();
+        allowed_headers.dedup();
+
+        Ok(Self {
+            any_origin: config.allowed_origins.iter().any(|origin| origin == "*"),
+            allowed_origins: config.allowed_origins,
+            allowed_headers: HeaderValue::try_from(allowed_headers.join(", "))?,
+            max_age: HeaderValue::from(config.max_age.as_secs()),
+        })
+    }
+
+    /// Returns value for `Access-Control-Allow-Origin` if request origin is allowed
+    pub fn get_allow_origin(&self, headers: &HeaderMap) -> Option<HeaderValue> {
+        if self.any_origin {
+            return Some(HeaderValue::from_static("*"));
+        }
+
+        headers.get(ORIGIN).and_then(|origin| {
+            self.allowed_origins
+                .iter()
+                .any(|allowed| allowed.as_bytes() == origin.as_bytes())
+                .then(|| origin.clone())
+        })
+    }
+
+    pub fn set_headers(&self, allow_origin: Option<HeaderValue>, headers: &mut HeaderMap) {
+        if !self.any_origin {
+            headers.append(VARY, HeaderValue::from_static("origin"));
+        }
+        if let Some(allow_origin) = allow_origin {
+            headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+            headers.insert(
+                ACCESS_CONTROL_ALLOW_METHODS,
+                HeaderValue::from_static("GET, HEAD, OPTIONS, POST"),
+            );
+            headers.insert(
+                ACCESS_CONTROL_EXPOSE_HEADERS,
+                HeaderValue::from_static("retry-after"),
+            );
+        }
+    }
+
+    /// Should be called only if origin is allowed
+    pub fn set_preflight_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, self.allowed_headers.clone());
+        headers.insert(ACCESS_CONTROL_MAX_AGE, self.max_age.clone());
+    }
+}
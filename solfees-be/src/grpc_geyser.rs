@@ -7,7 +7,9 @@ use {
     futures::stream::StreamExt,
     maplit::hashmap,
     serde::{Deserialize, Serialize},
-    solana_compute_budget::compute_budget_processor::process_compute_budget_instructions,
+    solana_compute_budget::compute_budget_processor::{
+        process_compute_budget_instructions, ComputeBudgetLimits,
+    },
     solana_sdk::{
         clock::{Epoch, Slot, UnixTimestamp},
         commitment_config::{CommitmentConfig, CommitmentLevel as CommitmentLevelSolana},
@@ -45,7 +47,9 @@ use {
     },
 };
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
 #[serde(rename_all = "lowercase")]
 pub enum CommitmentLevel {
     #[default]
@@ -90,7 +94,7 @@ impl CommitmentLevel {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct GeyserTransactionAccounts {
     pub writable: HashSet<Pubkey>,
     pub readable: HashSet<Pubkey>,
@@ -158,7 +162,7 @@ impl From<(&VersionedMessage, &TransactionStatusMeta)> for GeyserTransactionAcco
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct GeyserTransaction {
     pub signature: Signature,
     pub vote: bool,
@@ -170,31 +174,13 @@ pub struct GeyserTransaction {
     pub fee: u64,
 }
 
-impl TryFrom<(VersionedTransactionWithStatusMeta, bool)> for GeyserTransaction {
-    type Error = TransactionError;
-
-    fn try_from(
-        (
-            VersionedTransactionWithStatusMeta {
-                transaction: tx,
-                meta,
-            },
-            is_vote,
-        ): (VersionedTransactionWithStatusMeta, bool),
-    ) -> Result<Self, Self::Error> {
-        // 1 SOL = 10^9 lamports
-        // lamports per signature = 5_000
-        // default: 200k CU per instruction, 1.4M per tx
-        // max compute per account per block: 12M
-        // max compute per block: 48M
-
-        let account_keys = AccountKeys::new(
-            tx.message.static_account_keys(),
-            Some(&meta.loaded_addresses),
-        );
-
-        let instructions = tx
-            .message
+impl GeyserTransaction {
+    /// Number of signatures (including precompiles) and compute budget limits of the message
+    pub fn process_message(
+        message: &VersionedMessage,
+        account_keys: &AccountKeys,
+    ) -> Result<(u32, ComputeBudgetLimits), TransactionError> {
+        let instructions = message
             .instructions()
             .iter()
             .map(|ix| {
@@ -205,7 +191,7 @@ impl TryFrom<(VersionedTransactionWithStatusMeta, bool)> for GeyserTransaction {
             })
             .collect::<Result<Vec<_>, TransactionError>>()?;
 
-        let sigs_count = tx.signatures.len() as u32
+        let sigs_count = message.header().num_required_signatures as u32
             + instructions
                 .iter()
                 .map(|(program_id, ix)| {
@@ -220,6 +206,36 @@ impl TryFrom<(VersionedTransactionWithStatusMeta, bool)> for GeyserTransaction {
 
         let computed_budget_limits = process_compute_budget_instructions(instructions.into_iter())?;
 
+        Ok((sigs_count, computed_budget_limits))
+    }
+}
+
+impl TryFrom<(VersionedTransactionWithStatusMeta, bool)> for GeyserTransaction {
+    type Error = TransactionError;
+
+    fn try_from(
+        (
+            VersionedTransactionWithStatusMeta {
+                transaction: tx,
+                meta,
+            },
+            is_vote,
+        ): (VersionedTransactionWithStatusMeta, bool),
+    ) -> Result<Self, Self::Error> {
+        // 1 SOL = 10^9 lamports
+        // lamports per signature = 5_000
+        // default: 200k CU per instruction, 1.4M per tx
+        // max compute per account per block: 12M
+        // max compute per block: 48M
+
+        let account_keys = AccountKeys::new(
+            tx.message.static_account_keys(),
+            Some(&meta.loaded_addresses),
+        );
+
+        let (sigs_count, computed_budget_limits) =
+            Self::process_message(&tx.message, &account_keys)?;
+
         Ok(Self {
             signature: tx.signatures[0],
             vote: is_vote,
@@ -248,7 +264,44 @@ impl Default for BlockInfo {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Fees of non-vote transactions with compute unit limit, all fees are sorted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotSummary {
+    pub leader: Option<Pubkey>,
+    pub slot: Slot,
+    pub hash: Hash,
+    pub time: UnixTimestamp,
+    pub height: Slot,
+    pub parent_slot: Slot,
+    pub parent_hash: Hash,
+    pub total_transactions: u64,
+    pub total_transactions_vote: u64,
+    pub total_fee: u64,
+    pub total_units_consumed: u64,
+    pub fees: Vec<u64>,
+    pub writable_fees: HashMap<Pubkey, Vec<u64>>,
+    pub readable_fees: HashMap<Pubkey, Vec<u64>>,
+    pub transactions: Vec<SlotSummaryTransaction>, // used for filters in slots subscriptions
+}
+
+/// Accounts are sorted, lookup with `binary_search`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotSummaryTransaction {
+    pub writable: Vec<Pubkey>,
+    pub readable: Vec<Pubkey>,
+}
+
+impl SlotSummaryTransaction {
+    pub fn new(mut writable: Vec<Pubkey>, mut readable: Vec<Pubkey>) -> Self {
+        writable.sort_unstable();
+        writable.dedup();
+        readable.sort_unstable();
+        readable.dedup();
+        Self { writable, readable }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GeyserMessage {
     Status {
         slot: Slot,
@@ -264,14 +317,87 @@ pub enum GeyserMessage {
         parent_hash: Hash,
         transactions: Arc<Vec<GeyserTransaction>>,
     },
+    /// Aggregated `Slot`, see `GeyserMessage::into_summary`
+    SlotSummary(Arc<SlotSummary>),
 }
 
 impl GeyserMessage {
-    pub const fn slot(&self) -> Slot {
-        *match self {
-            Self::Status { slot, .. } => slot,
-            Self::Slot { slot, .. } => slot,
+    pub fn slot(&self) -> Slot {
+        match self {
+            Self::Status { slot, .. } => *slot,
+            Self::Slot { slot, .. } => *slot,
+            Self::SlotSummary(summary) => summary.slot,
+        }
+    }
+
+    /// Replace transactions in `Slot` with fees index, other messages are not changed
+    pub fn into_summary(self) -> Self {
+        let Self::Slot {
+            leader,
+            slot,
+            hash,
+            time,
+            height,
+            parent_slot,
+            parent_hash,
+            transactions,
+        } = self
+        else {
+            return self;
+        };
+
+        let mut fees = Vec::with_capacity(transactions.len());
+        let mut writable_fees = HashMap::<Pubkey, Vec<u64>>::new();
+        let mut readable_fees = HashMap::<Pubkey, Vec<u64>>::new();
+        let mut filtered = Vec::with_capacity(transactions.len());
+        for transaction in transactions
+            .iter()
+            .filter(|tx| !tx.vote && tx.unit_limit > 0)
+        {
+            fees.push(transaction.unit_price);
+            for account in transaction.accounts.writable.iter().copied() {
+                writable_fees
+                    .entry(account)
+                    .or_default()
+                    .push(transaction.unit_price);
+            }
+            for account in transaction.accounts.readable.iter().copied() {
+                readable_fees
+                    .entry(account)
+                    .or_default()
+                    .push(transaction.unit_price);
+            }
+            filtered.push(SlotSummaryTransaction::new(
+                transaction.accounts.writable.iter().copied().collect(),
+                transaction.accounts.readable.iter().copied().collect(),
+            ));
         }
+        fees.sort_unstable();
+        for fees in writable_fees.values_mut().chain(readable_fees.values_mut()) {
+            fees.sort_unstable();
+        }
+
+        Self::SlotSummary(Arc::new(SlotSummary {
+            leader,
+            slot,
+            hash,
+            time,
+            height,
+            parent_slot,
+            parent_hash,
+            total_transactions: transactions.len() as u64,
+            total_transactions_vote: transactions.iter().filter(|tx| tx.vote).count() as u64,
+            total_fee: transactions.iter().map(|tx| tx.fee).sum(),
+            total_units_consumed: transactions
+                .iter()
+                .filter(|tx| !tx.vote)
+                .map(|tx| tx.units_consumed.unwrap_or_default())
+                .sum(),
+            fees,
+            writable_fees,
+            readable_fees,
+            transactions: filtered,
+        }))
     }
 
     fn build_block(leader: Option<Pubkey>, block_info: BlockInfo) -> anyhow::Result<GeyserMessage> {
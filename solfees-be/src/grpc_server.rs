@@ -0,0 +1,432 @@
+use {
+    crate::{
+        auth::{ApiKeys, AuthError},
+        config::{ConfigListenGrpc, ConfigMetrics},
+        grpc_geyser::CommitmentLevel,
+        limits::{LimitKind, Limits},
+        metrics::solfees_be::{self as metrics, ClientId},
+        rpc_solana::{
+            LatestBlockhashRollback, ReqParamsSlotsSubscribeConfig, RpcRequestError,
+            RpcRequestsStats, SlotsSubscribeOutput, SolanaRpc, SolanaRpcMode,
+        },
+        tls::{Listener, TlsAcceptor},
+    },
+    futures::{channel::mpsc as futures_mpsc, sink::SinkExt},
+    hyper::{
+        body::Incoming as BodyIncoming, service::service_fn, Request as HttpRequest, StatusCode,
+    },
+    hyper_util::{
+        rt::tokio::{TokioExecutor, TokioIo},
+        server::{conn::auto::Builder as ServerBuilder, graceful::GracefulShutdown},
+    },
+    prost::Message,
+    std::{net::SocketAddr, sync::Arc, time::Instant},
+    tokio::sync::{broadcast, Notify},
+    tonic::{codec::CompressionEncoding, Code, Request, Response, Status},
+    tower::Service,
+    tracing::{debug, info},
+};
+
+pub mod proto {
+    #![allow(
+        clippy::clone_on_ref_ptr,
+        clippy::missing_const_for_fn,
+        clippy::trivially_copy_pass_by_ref
+    )]
+
+    tonic::include_proto!("solfees");
+}
+
+impl From<CommitmentLevel> for proto::CommitmentLevel {
+    fn from(commitment: CommitmentLevel) -> Self {
+        match commitment {
+            CommitmentLevel::Processed => Self::Processed,
+            CommitmentLevel::Confirmed => Self::Confirmed,
+            CommitmentLevel::Finalized => Self::Finalized,
+        }
+    }
+}
+
+impl From<proto::CommitmentLevel> for CommitmentLevel {
+    fn from(commitment: proto::CommitmentLevel) -> Self {
+        match commitment {
+            proto::CommitmentLevel::Processed => Self::Processed,
+            proto::CommitmentLevel::Confirmed => Self::Confirmed,
+            proto::CommitmentLevel::Finalized => Self::Finalized,
+        }
+    }
+}
+
+impl From<proto::SlotsFilter> for ReqParamsSlotsSubscribeConfig {
+    fn from(filter: proto::SlotsFilter) -> Self {
+        Self {
+            read_write: filter.read_write,
+            read_only: filter.read_only,
+            // out of range levels are rejected by filter validation
+            levels: filter
+                .levels
+                .into_iter()
+                .map(|level| u16::try_from(level).unwrap_or(u16::MAX))
+                .collect(),
+            skip_zeros: filter.skip_zeros,
+        }
+    }
+}
+
+impl From<SlotsSubscribeOutput> for proto::subscribe_slots_update::Update {
+    fn from(output: SlotsSubscribeOutput) -> Self {
+        match output {
+            SlotsSubscribeOutput::Status { slot, commitment } => Self::Status(proto::SlotStatus {
+                slot,
+                commitment: proto::CommitmentLevel::from(commitment) as i32,
+            }),
+            SlotsSubscribeOutput::Slot {
+                leader,
+                slot,
+                parent_slot,
+                commitment,
+                hash,
+                time,
+                height,
+                total_transactions_filtered,
+                total_transactions_vote,
+                total_transactions,
+                fee_average,
+                fee_levels,
+                total_fee,
+                total_units_consumed,
+            } => Self::Slot(proto::SlotFees {
+                leader,
+                slot,
+                parent_slot,
+                commitment: proto::CommitmentLevel::from(commitment) as i32,
+                hash,
+                time,
+                height,
+                total_transactions_filtered: total_transactions_filtered as u64,
+                total_transactions_vote: total_transactions_vote as u64,
+                total_transactions: total_transactions as u64,
+                fee_average,
+                fee_levels,
+                total_fee,
+                total_units_consumed,
+            }),
+        }
+    }
+}
+
+/// Remote address of the connection, added to request extensions
+#[derive(Debug, Clone, Copy)]
+struct RemoteAddr(SocketAddr);
+
+fn parse_commitment(commitment: Option<i32>) -> Result<CommitmentLevel, Status> {
+    match commitment {
+        Some(value) => proto::CommitmentLevel::try_from(value)
+            .map(Into::into)
+            .map_err(|_error| Status::invalid_argument(format!("invalid commitment: {value}"))),
+        None => Ok(CommitmentLevel::Finalized),
+    }
+}
+
+fn status_from_request_error(error: RpcRequestError) -> Status {
+    let code = match error.status() {
+        StatusCode::NOT_FOUND => Code::NotFound,
+        StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+        StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+        StatusCode::GATEWAY_TIMEOUT => Code::DeadlineExceeded,
+        status if status.is_client_error() => Code::InvalidArgument,
+        _ => Code::Internal,
+    };
+    Status::new(code, error.to_string())
+}
+
+fn status_limit_exceeded(kind: LimitKind) -> Status {
+    Status::resource_exhausted(format!("{} limit exceeded", kind.as_str()))
+}
+
+#[derive(Debug)]
+struct GrpcService {
+    solana_rpc: SolanaRpc,
+    config_metrics: Arc<ConfigMetrics>,
+    limits: Arc<Limits>,
+    api_keys: Arc<ApiKeys>,
+    streams_shutdown_tx: broadcast::Sender<()>,
+}
+
+impl GrpcService {
+    // gRPC is accounted as `solfees` API in API keys and metrics
+    const MODE: SolanaRpcMode = SolanaRpcMode::Solfees;
+
+    fn get_client_id<T>(&self, request: &Request<T>) -> Result<ClientId, Status> {
+        let headers = request.metadata().clone().into_headers();
+        let api_key = self
+            .api_keys
+            .authorize(None, &headers, None, Self::MODE)
+            .map_err(|error| match error {
+                AuthError::MissingKey | AuthError::InvalidKey => {
+                    Status::unauthenticated(error.as_str())
+                }
+                AuthError::ModeNotAllowed => Status::permission_denied(error.as_str()),
+            })?;
+        let RemoteAddr(remote_addr) = request
+            .extensions()
+            .get::<RemoteAddr>()
+            .copied()
+            .ok_or_else(|| Status::internal("remote address is not defined"))?;
+
+        Ok(ClientId::new(
+            &headers,
+            remote_addr.ip(),
+            &self.config_metrics,
+            api_key.as_deref(),
+        ))
+    }
+
+    fn check_limits(&self, client_id: &ClientId) -> Result<(), Status> {
+        self.limits
+            .check_request(client_id)
+            .map_err(status_limit_exceeded)?;
+        if self.limits.check_calls(client_id, 1) == 0 {
+            return Err(status_limit_exceeded(LimitKind::Calls));
+        }
+        Ok(())
+    }
+
+    fn create_response<T: Message>(
+        &self,
+        client_id: &ClientId,
+        stats: RpcRequestsStats,
+        ts: Instant,
+        result: Result<T, RpcRequestError>,
+    ) -> Result<Response<T>, Status> {
+        let status = match &result {
+            Ok(_) => StatusCode::OK,
+            Err(error) => error.status(),
+        };
+        metrics::requests_observe(Self::MODE, Some(status), ts.elapsed());
+
+        let message = result.map_err(status_from_request_error)?;
+        metrics::requests_call_inc(Self::MODE, stats);
+        // response is sent anyway, see `Limits::consume_egress`
+        let _ = self.limits.consume_egress(client_id, message.encoded_len());
+        Ok(Response::new(message))
+    }
+}
+
+#[tonic::async_trait]
+impl proto::solfees_server::Solfees for GrpcService {
+    async fn get_recent_prioritization_fees(
+        &self,
+        request: Request<proto::GetRecentPrioritizationFeesRequest>,
+    ) -> Result<Response<proto::GetRecentPrioritizationFeesResponse>, Status> {
+        let ts = Instant::now();
+        let client_id = self.get_client_id(&request)?;
+        self.check_limits(&client_id)?;
+
+        let filter = request.into_inner().filter.unwrap_or_default();
+        let result = self
+            .solana_rpc
+            .get_recent_prioritization_fees(&client_id, filter.into())
+            .await
+            .map(|outputs| proto::GetRecentPrioritizationFeesResponse {
+                slots: outputs
+                    .into_iter()
+                    .filter_map(|output| match output.into() {
+                        proto::subscribe_slots_update::Update::Slot(slot) => Some(slot),
+                        proto::subscribe_slots_update::Update::Status(_) => None,
+                    })
+                    .collect(),
+            });
+
+        let stats = RpcRequestsStats {
+            recent_prioritization_fees: 1,
+            ..Default::default()
+        };
+        self.create_response(&client_id, stats, ts, result)
+    }
+
+    async fn get_latest_blockhash(
+        &self,
+        request: Request<proto::GetLatestBlockhashRequest>,
+    ) -> Result<Response<proto::GetLatestBlockhashResponse>, Status> {
+        let ts = Instant::now();
+        let client_id = self.get_client_id(&request)?;
+        self.check_limits(&client_id)?;
+
+        let request = request.into_inner();
+        let commitment = parse_commitment(request.commitment)?;
+        let rollback = LatestBlockhashRollback::new(
+            request.rollback as usize,
+            request.rollback_ms,
+            request.remaining_validity,
+        )
+        .map_err(|error| Status::invalid_argument(error.message))?;
+        let result = self
+            .solana_rpc
+            .get_latest_blockhash(&client_id, commitment, rollback, request.min_context_slot)
+            .await
+            .map(|response| proto::GetLatestBlockhashResponse {
+                slot: response.context.slot,
+                blockhash: response.value.blockhash,
+                last_valid_block_height: response.value.last_valid_block_height,
+            });
+
+        let stats = RpcRequestsStats {
+            latest_blockhash: 1,
+            ..Default::default()
+        };
+        self.create_response(&client_id, stats, ts, result)
+    }
+
+    async fn get_leader_schedule(
+        &self,
+        request: Request<proto::GetLeaderScheduleRequest>,
+    ) -> Result<Response<proto::GetLeaderScheduleResponse>, Status> {
+        let ts = Instant::now();
+        let client_id = self.get_client_id(&request)?;
+        self.check_limits(&client_id)?;
+
+        let request = request.into_inner();
+        let commitment = parse_commitment(request.commitment)?;
+        let result = self
+            .solana_rpc
+            .get_leader_schedule(&client_id, request.slot, commitment, request.identity)
+            .await
+            .and_then(|maybe_schedule| {
+                maybe_schedule
+                    .ok_or(RpcRequestError::NotFound)
+                    .map(|schedule| proto::GetLeaderScheduleResponse {
+                        leaders: schedule
+                            .into_iter()
+                            .map(|(identity, slots)| {
+                                let slots = slots.into_iter().map(|slot| slot as u64).collect();
+                                (identity, proto::LeaderSlots { slots })
+                            })
+                            .collect(),
+                    })
+            });
+
+        let stats = RpcRequestsStats {
+            leader_schedule: 1,
+            ..Default::default()
+        };
+        self.create_response(&client_id, stats, ts, result)
+    }
+
+    type SubscribeSlotsStream = futures_mpsc::Receiver<Result<proto::SubscribeSlotsUpdate, Status>>;
+
+    async fn subscribe_slots(
+        &self,
+        request: Request<proto::SubscribeSlotsRequest>,
+    ) -> Result<Response<Self::SubscribeSlotsStream>, Status> {
+        let client_id = self.get_client_id(&request)?;
+        self.limits
+            .check_request(&client_id)
+            .map_err(status_limit_exceeded)?;
+
+        let filter = request.into_inner().filter.unwrap_or_default();
+        let mut subscription = self
+            .solana_rpc
+            .slots_subscribe(filter.into())
+            .map_err(status_from_request_error)?;
+        let permit = self
+            .limits
+            .acquire_websocket(&client_id)
+            .map_err(status_limit_exceeded)?;
+
+        let (mut updates_tx, updates_rx) = futures_mpsc::channel(16);
+        let mut shutdown_rx = self.streams_shutdown_tx.subscribe();
+        let limits = Arc::clone(&self.limits);
+        tokio::spawn(async move {
+            let _permit = permit;
+            loop {
+                let update = tokio::select! {
+                    _ = shutdown_rx.recv() => break,
+                    maybe_output = subscription.recv(&client_id) => match maybe_output {
+                        Ok(output) => proto::SubscribeSlotsUpdate {
+                            update: Some(output.into()),
+                        },
+                        Err(broadcast::error::RecvError::Closed) => break,
+                        Err(broadcast::error::RecvError::Lagged(_)) => {
+                            let status = Status::data_loss("subscription lagged");
+                            let _ = updates_tx.send(Err(status)).await;
+                            break;
+                        }
+                    },
+                };
+
+                let size = update.encoded_len();
+                if limits.consume_egress(&client_id, size).is_err() {
+                    metrics::limits_rejected_inc(&client_id, LimitKind::Egress);
+                    let status = status_limit_exceeded(LimitKind::Egress);
+                    let _ = updates_tx.send(Err(status)).await;
+                    break;
+                }
+                client_id.observe_egress_ws(size as u64);
+                // receiver is dropped when client is disconnected
+                if updates_tx.send(Ok(update)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(updates_rx))
+    }
+}
+
+pub async fn run_grpc(
+    config: ConfigListenGrpc,
+    solana_rpc: SolanaRpc,
+    config_metrics: Arc<ConfigMetrics>,
+    limits: Arc<Limits>,
+    api_keys: Arc<ApiKeys>,
+    tls: Option<Arc<TlsAcceptor>>,
+    shutdown: Arc<Notify>,
+) -> anyhow::Result<()> {
+    let addr = config.bind;
+    let tls_enabled = tls.is_some();
+    let mut listener = Listener::bind(addr, tls).await?;
+    info!(%addr, tls = tls_enabled, "Start Solfees gRPC server");
+
+    let (streams_shutdown_tx, _streams_shutdown_rx) = broadcast::channel(1);
+    let service = proto::solfees_server::SolfeesServer::new(GrpcService {
+        solana_rpc,
+        config_metrics,
+        limits,
+        api_keys,
+        streams_shutdown_tx: streams_shutdown_tx.clone(),
+    })
+    .accept_compressed(CompressionEncoding::Gzip)
+    .send_compressed(CompressionEncoding::Gzip);
+
+    let http = ServerBuilder::new(TokioExecutor::new());
+    let graceful = GracefulShutdown::new();
+    loop {
+        let (stream, remote_addr) = tokio::select! {
+            () = shutdown.notified() => break,
+            maybe_incoming = listener.accept() => maybe_incoming?,
+        };
+
+        let service = service.clone();
+        let connection = http.serve_connection(
+            TokioIo::new(stream),
+            service_fn(move |mut req: HttpRequest<BodyIncoming>| {
+                req.extensions_mut().insert(RemoteAddr(remote_addr));
+                service.clone().call(req)
+            }),
+        );
+        let connection = graceful.watch(connection.into_owned());
+        tokio::spawn(async move {
+            if let Err(error) = connection.await {
+                debug!(error, "connection error");
+            }
+        });
+    }
+
+    drop(listener);
+    // service is still referenced by connections, so streams are stopped explicitly
+    let _ = streams_shutdown_tx.send(());
+    graceful.shutdown().await;
+
+    Ok::<(), anyhow::Error>(())
+}
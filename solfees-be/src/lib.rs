@@ -1,10 +1,16 @@
+pub mod app;
+pub mod auth;
+pub mod bus;
 pub mod cli;
 pub mod config;
+pub mod cors;
 pub mod grpc_geyser;
+pub mod grpc_server;
+pub mod limits;
 pub mod metrics;
-pub mod redis;
 pub mod rpc_server;
 pub mod rpc_solana;
 pub mod schedule;
+pub mod tls;
 pub mod tracing;
 pub mod version;
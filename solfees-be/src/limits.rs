@@ -0,0 +1,279 @@
+use {
+    crate::{
+        config::{ConfigLimits, ConfigLimitsRate},
+        metrics::solfees_be::{self as metrics, ClientId},
+    },
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    Requests,
+    Calls,
+    BatchCalls,
+    WebSockets,
+    Egress,
+}
+
+impl LimitKind {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Requests => "requests",
+            Self::Calls => "calls",
+            Self::BatchCalls => "batch_calls",
+            Self::WebSockets => "websockets",
+            Self::Egress => "egress",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    const fn new(config: ConfigLimitsRate, now: Instant) -> Self {
+        Self {
+            tokens: config.burst as f64,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, config: ConfigLimitsRate, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * config.per_second as f64).min(config.burst as f64);
+        self.updated_at = now;
+    }
+
+    fn is_full(&self, config: ConfigLimitsRate, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens + elapsed * config.per_second as f64 >= config.burst as f64
+    }
+}
+
+#[derive(Debug)]
+struct ClientLimits {
+    requests: TokenBucket,
+    calls: TokenBucket,
+    egress: TokenBucket,
+    websockets: usize,
+}
+
+impl ClientLimits {
+    fn new(config: &ConfigLimits, now: Instant) -> Self {
+        let create = |config: Option<ConfigLimitsRate>| {
+            TokenBucket::new(config.unwrap_or(ConfigLimitsRate::ZERO), now)
+        };
+
+        Self {
+            requests: create(config.requests),
+            calls: create(config.calls),
+            egress: create(config.egress),
+            websockets: 0,
+        }
+    }
+
+    fn is_idle(&self, config: &ConfigLimits, now: Instant) -> bool {
+        let is_full = |bucket: &TokenBucket, config: Option<ConfigLimitsRate>| {
+            config.map_or(true, |config| bucket.is_full(config, now))
+        };
+
+        self.websockets == 0
+            && is_full(&self.requests, config.requests)
+            && is_full(&self.calls, config.calls)
+            && is_full(&self.egress, config.egress)
+    }
+}
+
+#[derive(Debug)]
+struct LimitsClients {
+    map: HashMap<String, ClientLimits>,
+    cleanup_at: Instant,
+}
+
+/// Per client limits, client identified by `ClientId::limits_key`
+#[derive(Debug)]
+pub struct Limits {
+    config: ConfigLimits,
+    clients: Mutex<LimitsClients>,
+}
+
+impl Limits {
+    pub fn new(config: ConfigLimits) -> Self {
+        Self {
+            config,
+            clients: Mutex::new(LimitsClients {
+                map: HashMap::new(),
+                cleanup_at: Instant::now() + CLEANUP_INTERVAL,
+            }),
+        }
+    }
+
+    const fn is_enabled(&self) -> bool {
+        self.config.requests.is_some()
+            || self.config.calls.is_some()
+            || self.config.egress.is_some()
+            || self.config.websockets.is_some()
+    }
+
+    fn with_client<T>(
+        &self,
+        client_id: &ClientId,
+        f: impl FnOnce(&ConfigLimits, &mut ClientLimits, Instant) -> T,
+    ) -> T {
+        let now = Instant::now();
+        let mut locked = self.clients.lock().expect("unpoisoned mutex");
+
+        if locked.cleanup_at <= now {
+            locked
+                .map
+                .retain(|_key, client| !client.is_idle(&self.config, now));
+            locked.cleanup_at = now + CLEANUP_INTERVAL;
+        }
+
+        let client = locked
+            .map
+            .entry(client_id.limits_key().to_owned())
+            .or_insert_with(|| ClientLimits::new(&self.config, now));
+        f(&self.config, client, now)
+    }
+
+    /// Consume one token from requests bucket, also reject if egress is exhausted
+    pub fn check_request(&self, client_id: &ClientId) -> Result<(), LimitKind> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let result = self.with_client(client_id, |config, client, now| {
+            if let Some(config) = config.egress {
+                client.egress.refill(config, now);
+                if client.egress.tokens < 0.0 {
+                    return Err(LimitKind::Egress);
+                }
+            }
+
+            if let Some(config) = config.requests {
+                client.requests.refill(config, now);
+                if client.requests.tokens < 1.0 {
+                    return Err(LimitKind::Requests);
+                }
+                client.requests.tokens -= 1.0;
+            }
+
+            Ok(())
+        });
+        if let Err(kind) = result {
+            metrics::limits_rejected_inc(client_id, kind);
+        }
+        result
+    }
+
+    /// Cap of calls in one batch, checked before `check_calls` so rejected batch does not consume tokens
+    pub fn check_batch_calls(&self, client_id: &ClientId, calls: usize) -> Result<(), usize> {
+        match self.config.batch_calls {
+            Some(max) if calls > max => {
+                metrics::limits_rejected_inc(client_id, LimitKind::BatchCalls);
+                Err(max)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns number of allowed calls, tokens are consumed only for allowed calls
+    pub fn check_calls(&self, client_id: &ClientId, calls: usize) -> usize {
+        if self.config.calls.is_none() {
+            return calls;
+        }
+
+        let allowed = self.with_client(client_id, |config, client, now| {
+            let Some(config) = config.calls else {
+                return calls;
+            };
+
+            client.calls.refill(config, now);
+            let allowed = (client.calls.tokens.max(0.0) as usize).min(calls);
+            client.calls.tokens -= allowed as f64;
+            allowed
+        });
+        if allowed < calls {
+            metrics::limits_rejected_inc(client_id, LimitKind::Calls);
+        }
+        allowed
+    }
+
+    /// Egress is always consumed (bucket can go below zero), error returned if bucket is exhausted,
+    /// rejection is not recorded because data already sent. HTTP responses are sent anyway,
+    /// following requests are rejected by `check_request` until bucket is refilled;
+    /// streams (WebSocket, SSE, gRPC) are closed on error
+    pub fn consume_egress(&self, client_id: &ClientId, bytes: usize) -> Result<(), LimitKind> {
+        if self.config.egress.is_none() {
+            return Ok(());
+        }
+
+        self.with_client(client_id, |config, client, now| {
+            let Some(config) = config.egress else {
+                return Ok(());
+            };
+
+            client.egress.refill(config, now);
+            client.egress.tokens -= bytes as f64;
+            if client.egress.tokens < 0.0 {
+                Err(LimitKind::Egress)
+            } else {
+                Ok(())
+            }
+        })
+    }
+
+    pub fn acquire_websocket(
+        self: &Arc<Self>,
+        client_id: &ClientId,
+    ) -> Result<LimitsWebSocketPermit, LimitKind> {
+        let Some(max) = self.config.websockets else {
+            return Ok(LimitsWebSocketPermit {
+                limits: None,
+                client_id: client_id.clone(),
+            });
+        };
+
+        self.with_client(client_id, |_config, client, _now| {
+            if client.websockets >= max {
+                Err(LimitKind::WebSockets)
+            } else {
+                client.websockets += 1;
+                Ok(())
+            }
+        })
+        .inspect_err(|kind| metrics::limits_rejected_inc(client_id, *kind))?;
+
+        Ok(LimitsWebSocketPermit {
+            limits: Some(Arc::clone(self)),
+            client_id: client_id.clone(),
+        })
+    }
+}
+
+/// Decrement number of WebSocket connections on drop
+#[derive(Debug)]
+pub struct LimitsWebSocketPermit {
+    limits: Option<Arc<Limits>>,
+    client_id: ClientId,
+}
+
+impl Drop for LimitsWebSocketPermit {
+    fn drop(&mut self) {
+        if let Some(limits) = self.limits.take() {
+            limits.with_client(&self.client_id, |_config, client, _now| {
+                client.websockets = client.websockets.saturating_sub(1);
+            });
+        }
+    }
+}
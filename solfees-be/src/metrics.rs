@@ -3,7 +3,7 @@ use {
     http_body_util::{combinators::BoxBody, BodyExt, Full as FullBody},
     hyper::body::Bytes,
     prometheus::{IntCounterVec, Opts, Registry, TextEncoder},
-    std::convert::Infallible,
+    std::{convert::Infallible, sync::Once},
     tracing::error,
 };
 
@@ -24,19 +24,28 @@ macro_rules! register {
     };
 }
 
+// can be called more than once, `solfees-all-in-one` init metrics for both modules
 fn init2() {
-    register!(VERSION);
-    VERSION
-        .with_label_values(&[
-            VERSION_INFO.buildts,
-            VERSION_INFO.git,
-            VERSION_INFO.package,
-            VERSION_INFO.proto,
-            VERSION_INFO.rustc,
-            VERSION_INFO.solana,
-            VERSION_INFO.version,
-        ])
-        .inc();
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        register!(VERSION);
+        VERSION
+            .with_label_values(&[
+                VERSION_INFO.buildts,
+                VERSION_INFO.git,
+                VERSION_INFO.package,
+                VERSION_INFO.proto,
+                VERSION_INFO.rustc,
+                VERSION_INFO.solana,
+                VERSION_INFO.version,
+            ])
+            .inc();
+    });
+}
+
+pub fn init_all_in_one() {
+    grpc2redis::init();
+    solfees_be::init();
 }
 
 pub fn collect_to_body() -> BoxBody<Bytes, Infallible> {
@@ -53,7 +62,7 @@ pub mod grpc2redis {
     use {
         super::{init2, REGISTRY},
         crate::grpc_geyser::CommitmentLevel,
-        prometheus::{IntCounter, IntGaugeVec, Opts},
+        prometheus::{IntCounter, IntGauge, IntGaugeVec, Opts},
         solana_sdk::clock::Slot,
     };
 
@@ -70,6 +79,14 @@ pub mod grpc2redis {
         static ref REDIS_MESSAGES_PUSHED: IntCounter = IntCounter::new(
             "redis_messages_pushed_total", "Number of messages pushed to Redis stream"
         ).unwrap();
+
+        static ref REDIS_MESSAGES_DROPPED: IntCounter = IntCounter::new(
+            "redis_messages_dropped_total", "Number of messages older than finalized slot in Redis"
+        ).unwrap();
+
+        static ref REDIS_LEADER: IntGauge = IntGauge::new(
+            "redis_leader", "1 if instance pushes messages to Redis, 0 if standby"
+        ).unwrap();
     }
 
     pub fn init() {
@@ -78,6 +95,8 @@ pub mod grpc2redis {
         register!(GRPC_BLOCK_BUILD_FAILED);
         register!(REDIS_SLOT_PUSHED);
         register!(REDIS_MESSAGES_PUSHED);
+        register!(REDIS_MESSAGES_DROPPED);
+        register!(REDIS_LEADER);
     }
 
     pub fn grpc_block_build_failed_inc() {
@@ -93,21 +112,35 @@ pub mod grpc2redis {
     pub fn redis_messages_pushed_inc_by(delta: usize) {
         REDIS_MESSAGES_PUSHED.inc_by(delta as u64);
     }
+
+    pub fn redis_messages_dropped_inc_by(delta: usize) {
+        REDIS_MESSAGES_DROPPED.inc_by(delta as u64);
+    }
+
+    pub fn redis_leader_set(leader: bool) {
+        REDIS_LEADER.set(leader as i64);
+    }
 }
 
 pub mod solfees_be {
     use {
         super::{init2, REGISTRY},
         crate::{
+            auth::ApiKey,
             config::ConfigMetrics,
             grpc_geyser::CommitmentLevel,
+            limits::LimitKind,
             rpc_solana::{RpcRequestsStats, SolanaRpcMode},
         },
         http::{HeaderMap, StatusCode},
-        prometheus::{HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts},
+        prometheus::{
+            Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+            IntGaugeVec, Opts,
+        },
         solana_sdk::clock::Slot,
         std::{
             borrow::Cow,
+            net::IpAddr,
             sync::Arc,
             time::{Duration, Instant},
         },
@@ -129,11 +162,30 @@ pub mod solfees_be {
             &["api", "status"]
         ).unwrap();
 
+        static ref REDIS_READ_DURATION_SECONDS: Histogram = Histogram::with_opts(
+            HistogramOpts::new("redis_read_duration_seconds", "Elapsed time per Redis stream read")
+                .buckets(vec![0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0])
+        ).unwrap();
+
+        static ref REDIS_READ_BATCH_SIZE: Histogram = Histogram::with_opts(
+            HistogramOpts::new("redis_read_batch_size", "Number of messages per Redis stream read")
+                .buckets(vec![1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0])
+        ).unwrap();
+
+        static ref BUS_DECODE_FAILED_TOTAL: IntCounter = IntCounter::new(
+            "bus_decode_failed_total", "Total number of skipped messages which can not be decoded"
+        ).unwrap();
+
         static ref REQUESTS_CALLS_TOTAL: IntCounterVec = IntCounterVec::new(
             Opts::new("requests_calls_total", "Total number of request calls by API and method"),
             &["api", "method"]
         ).unwrap();
 
+        static ref REQUESTS_CACHE_TOTAL: IntCounterVec = IntCounterVec::new(
+            Opts::new("requests_cache_total", "Total number of cache lookups by method and result"),
+            &["method", "result"]
+        ).unwrap();
+
         static ref REQUESTS_QUEUE_SIZE: IntGauge = IntGauge::new(
             "requests_queue_size", "Queue size by API and method"
         ).unwrap();
@@ -149,9 +201,14 @@ pub mod solfees_be {
         ).unwrap();
 
         static ref CLIENT_USAGE_EGRESS_WS_TOTAL: IntCounterVec = IntCounterVec::new(
-            Opts::new("client_usage_egress_total", "Total number of bytes sent over WebSocket"),
+            Opts::new("client_usage_egress_total", "Total number of bytes sent over WebSocket and SSE"),
             &["client_id", "subscription_id"]
         ).unwrap();
+
+        static ref CLIENT_LIMITS_REJECTED_TOTAL: IntCounterVec = IntCounterVec::new(
+            Opts::new("client_limits_rejected_total", "Total number of rejections by client limits"),
+            &["client_id", "subscription_id", "limit"]
+        ).unwrap();
     }
 
     pub fn init() {
@@ -159,11 +216,16 @@ pub mod solfees_be {
 
         register!(LATEST_SLOT);
         register!(REQUESTS_DURATION_SECONDS);
+        register!(REDIS_READ_DURATION_SECONDS);
+        register!(REDIS_READ_BATCH_SIZE);
+        register!(BUS_DECODE_FAILED_TOTAL);
         register!(REQUESTS_CALLS_TOTAL);
+        register!(REQUESTS_CACHE_TOTAL);
         register!(REQUESTS_QUEUE_SIZE);
         register!(WEBSOCKETS_ALIVE_TOTAL);
         register!(CLIENT_USAGE_CPU_TOTAL);
         register!(CLIENT_USAGE_EGRESS_WS_TOTAL);
+        register!(CLIENT_LIMITS_REJECTED_TOTAL);
     }
 
     pub fn set_slot(commitment: CommitmentLevel, slot: Slot) {
@@ -190,7 +252,28 @@ pub mod solfees_be {
             .observe(sec);
     }
 
+    pub fn redis_read_observe(duration: Duration, size: usize) {
+        REDIS_READ_DURATION_SECONDS.observe(duration.as_secs_f64());
+        REDIS_READ_BATCH_SIZE.observe(size as f64);
+    }
+
+    pub fn bus_decode_failed_inc() {
+        BUS_DECODE_FAILED_TOTAL.inc();
+    }
+
     pub fn requests_call_inc(api: SolanaRpcMode, stats: RpcRequestsStats) {
+        REQUESTS_CALLS_TOTAL
+            .with_label_values(&[api.as_str(), "get_block_height"])
+            .inc_by(stats.block_height);
+        REQUESTS_CALLS_TOTAL
+            .with_label_values(&[api.as_str(), "get_block_time"])
+            .inc_by(stats.block_time);
+        REQUESTS_CALLS_TOTAL
+            .with_label_values(&[api.as_str(), "get_blocks"])
+            .inc_by(stats.blocks);
+        REQUESTS_CALLS_TOTAL
+            .with_label_values(&[api.as_str(), "get_fee_for_message"])
+            .inc_by(stats.fee_for_message);
         REQUESTS_CALLS_TOTAL
             .with_label_values(&[api.as_str(), "get_latest_blockhash"])
             .inc_by(stats.latest_blockhash);
@@ -206,6 +289,15 @@ pub mod solfees_be {
         REQUESTS_CALLS_TOTAL
             .with_label_values(&[api.as_str(), "get_version"])
             .inc_by(stats.version);
+        REQUESTS_CALLS_TOTAL
+            .with_label_values(&[api.as_str(), "is_blockhash_valid"])
+            .inc_by(stats.is_blockhash_valid);
+    }
+
+    pub fn requests_cache_observe(method: &str, hit: bool) {
+        REQUESTS_CACHE_TOTAL
+            .with_label_values(&[method, if hit { "hit" } else { "miss" }])
+            .inc();
     }
 
     pub fn requests_queue_size_inc() {
@@ -234,10 +326,21 @@ pub mod solfees_be {
         WEBSOCKETS_ALIVE_TOTAL.with_label_values(&[api]).dec()
     }
 
+    pub fn limits_rejected_inc(client_id: &ClientId, kind: LimitKind) {
+        CLIENT_LIMITS_REJECTED_TOTAL
+            .with_label_values(&[
+                &client_id.inner.client_id,
+                &client_id.inner.subsription_id,
+                kind.as_str(),
+            ])
+            .inc();
+    }
+
     #[derive(Debug)]
     struct ClientIdInner {
         client_id: String,
         subsription_id: String,
+        limits_key: String,
     }
 
     #[derive(Debug, Clone)]
@@ -246,14 +349,31 @@ pub mod solfees_be {
     }
 
     impl ClientId {
-        pub fn new(headers: &HeaderMap, config_metrics: &ConfigMetrics) -> Self {
+        pub fn new(
+            headers: &HeaderMap,
+            remote_ip: IpAddr,
+            config_metrics: &ConfigMetrics,
+            api_key: Option<&ApiKey>,
+        ) -> Self {
+            // ids from API key take precedence over headers
+            let (client_id, subsription_id) = match api_key {
+                Some(api_key) => (api_key.client_id.clone(), api_key.subscription_id.clone()),
+                None => (
+                    Self::get(headers, config_metrics.usage_client_id.as_deref()),
+                    Self::get(headers, config_metrics.usage_subscription_id.as_deref()),
+                ),
+            };
+            let limits_key = if client_id.is_empty() {
+                format!("ip:{remote_ip}")
+            } else {
+                format!("id:{client_id}")
+            };
+
             Self {
                 inner: Arc::new(ClientIdInner {
-                    client_id: Self::get(headers, config_metrics.usage_client_id.as_deref()),
-                    subsription_id: Self::get(
-                        headers,
-                        config_metrics.usage_subscription_id.as_deref(),
-                    ),
+                    client_id,
+                    subsription_id,
+                    limits_key,
                 }),
             }
         }
@@ -268,6 +388,10 @@ pub mod solfees_be {
             })
         }
 
+        pub fn limits_key(&self) -> &str {
+            &self.inner.limits_key
+        }
+
         pub fn start_timer_cpu(&self) -> ClientIdTimerCpu {
             ClientIdTimerCpu::new(self)
         }
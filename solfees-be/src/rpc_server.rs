@@ -1,33 +1,327 @@
 use {
     crate::{
-        config::ConfigMetrics,
+        auth::{ApiKeys, AuthError},
+        config::{ConfigListenRpc, ConfigMetrics},
+        cors::Cors,
+        limits::{LimitKind, Limits},
         metrics::{self, solfees_be as metrics_be},
-        rpc_solana::{SolanaRpc, SolanaRpcMode},
+        rpc_solana::{RpcRequestError, SolanaRpc, SolanaRpcMode},
+        tls::{Listener, TlsAcceptor},
+    },
+    futures::{channel::mpsc as futures_mpsc, future::TryFutureExt, stream::StreamExt},
+    http_body_util::{
+        combinators::BoxBody, BodyExt, Empty as BodyEmpty, Full as BodyFull, LengthLimitError,
+        Limited, StreamBody,
     },
-    futures::future::TryFutureExt,
-    http_body_util::{BodyExt, Empty as BodyEmpty, Full as BodyFull, Limited},
     hyper::{
-        body::{Bytes, Incoming as BodyIncoming},
-        header::{ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_TYPE},
+        body::{Bytes, Frame, Incoming as BodyIncoming},
+        header::{
+            HeaderMap, ACCEPT_ENCODING, ALLOW, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_TYPE, ETAG,
+            IF_NONE_MATCH, RETRY_AFTER, VARY,
+        },
         service::service_fn,
-        Request, Response, StatusCode,
+        Method, Request, Response, StatusCode,
+    },
+    hyper_tungstenite::{
+        is_upgrade_request,
+        tungstenite::{protocol::WebSocketConfig, Message as WebSocketMessage},
     },
-    hyper_tungstenite::{is_upgrade_request, tungstenite::protocol::WebSocketConfig},
     hyper_util::{
         rt::tokio::{TokioExecutor, TokioIo},
         server::{conn::auto::Builder as ServerBuilder, graceful::GracefulShutdown},
     },
-    std::{net::SocketAddr, sync::Arc, time::Instant},
-    tokio::{
-        net::TcpListener,
-        sync::{broadcast, Notify},
+    std::{
+        convert::Infallible,
+        io::Write,
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::Instant,
     },
+    tokio::sync::{broadcast, Notify},
     tracing::{debug, error, info},
 };
 
-pub async fn run_admin(addr: SocketAddr, shutdown: Arc<Notify>) -> anyhow::Result<()> {
-    let listener = TcpListener::bind(addr).await?;
-    info!(%addr, "Start Admin RPC server");
+const LAST_EVENT_ID: &str = "last-event-id";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ContentEncoding {
+    // sorted by preference, used if quality values are equal
+    Zstd,
+    Brotli,
+    Gzip,
+}
+
+impl ContentEncoding {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Zstd => "zstd",
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    const ALL: [Self; 3] = [Self::Zstd, Self::Brotli, Self::Gzip];
+
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        // `q=0` is kept to exclude encoding from `*`
+        let mut explicit: Vec<(Self, f32)> = vec![];
+        let mut wildcard: Option<f32> = None;
+        for value in headers.get_all(ACCEPT_ENCODING) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+
+            for item in value.split(',') {
+                let mut params = item.split(';');
+                let encoding = match params.next().map(str::trim) {
+                    Some("*") => None,
+                    Some(name) => match Self::from_name(name) {
+                        Some(encoding) => Some(encoding),
+                        None => continue,
+                    },
+                    None => continue,
+                };
+                let quality = params
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |quality| quality.parse::<f32>().ok());
+                let Some(quality) = quality else {
+                    continue;
+                };
+
+                match encoding {
+                    Some(encoding) => explicit.push((encoding, quality)),
+                    None => wildcard = Some(quality),
+                }
+            }
+        }
+
+        let wildcard = wildcard.into_iter().flat_map(|quality| {
+            Self::ALL
+                .into_iter()
+                .filter(|encoding| explicit.iter().all(|(value, _)| value != encoding))
+                .map(move |encoding| (encoding, quality))
+        });
+        let mut selected: Option<(Self, f32)> = None;
+        for (encoding, quality) in explicit.iter().copied().chain(wildcard) {
+            if quality <= 0.0 {
+                continue;
+            }
+            selected = match selected {
+                Some((current, current_quality))
+                    if current_quality > quality
+                        || (current_quality == quality && current < encoding) =>
+                {
+                    Some((current, current_quality))
+                }
+                _ => Some((encoding, quality)),
+            };
+        }
+        selected.map(|(encoding, _quality)| encoding)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("zstd") {
+            Some(Self::Zstd)
+        } else if name.eq_ignore_ascii_case("br") {
+            Some(Self::Brotli)
+        } else if name.eq_ignore_ascii_case("gzip") || name.eq_ignore_ascii_case("x-gzip") {
+            Some(Self::Gzip)
+        } else {
+            None
+        }
+    }
+
+    fn encode(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::Zstd => zstd::bulk::compress(data, zstd::DEFAULT_COMPRESSION_LEVEL),
+            Self::Brotli => {
+                // quality 4 / window 22: close to gzip speed with better ratio
+                let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 4, 22);
+                encoder.write_all(data)?;
+                Ok(encoder.into_inner())
+            }
+            Self::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(
+                    Vec::with_capacity(data.len() / 4),
+                    flate2::Compression::fast(),
+                );
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// Opt-in compression of WebSocket messages, selected with `?compression=` on connect:
+/// messages not less than threshold are sent as binary, smaller messages as text
+#[derive(Debug, Clone, Copy)]
+pub struct WebSocketCompression {
+    encoding: ContentEncoding,
+    threshold: usize,
+}
+
+impl WebSocketCompression {
+    fn from_query(query: Option<&str>, threshold: usize) -> Result<Option<Self>, String> {
+        let Some((_, name)) = form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+            .find(|(key, _value)| key == "compression")
+        else {
+            return Ok(None);
+        };
+        match ContentEncoding::from_name(&name) {
+            Some(encoding) => Ok(Some(Self {
+                encoding,
+                threshold,
+            })),
+            None => Err(format!("unknown compression: {name}")),
+        }
+    }
+
+    // compression is executed on blocking pool, same as for HTTP responses
+    pub async fn encode(compression: Option<Self>, message: String) -> WebSocketMessage {
+        let Some(Self { encoding, .. }) =
+            compression.filter(|compression| message.len() >= compression.threshold)
+        else {
+            return WebSocketMessage::Text(message);
+        };
+
+        let message = Bytes::from(message.into_bytes());
+        let data = message.clone();
+        let error = match tokio::task::spawn_blocking(move || encoding.encode(&data)).await {
+            Ok(Ok(data)) => return WebSocketMessage::Binary(data),
+            Ok(Err(error)) => error.to_string(),
+            Err(error) => error.to_string(),
+        };
+        error!(
+            %error,
+            encoding = encoding.as_str(),
+            "failed to compress WebSocket message"
+        );
+        WebSocketMessage::Text(String::from_utf8(message.into()).expect("valid utf8"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReqType {
+    Rpc,
+    Rest,
+    Sse,
+    WebSocket,
+}
+
+fn parse_route(path: &str, is_upgrade: bool) -> Option<(ReqType, SolanaRpcMode)> {
+    match path {
+        "/api/solana" => Some((ReqType::Rpc, SolanaRpcMode::Solana)),
+        "/api/solana/triton" => Some((ReqType::Rpc, SolanaRpcMode::Triton)),
+        "/api/solana/solfees" => Some((ReqType::Rpc, SolanaRpcMode::Solfees)),
+        "/api/solana/solfees/ws" if is_upgrade => {
+            Some((ReqType::WebSocket, SolanaRpcMode::Solfees))
+        }
+        "/api/solana/solfees/sse" => Some((ReqType::Sse, SolanaRpcMode::Solfees)),
+
+        "/api/solfees" => Some((ReqType::Rpc, SolanaRpcMode::SolfeesFrontend)),
+        "/api/solfees/ws" if is_upgrade => {
+            Some((ReqType::WebSocket, SolanaRpcMode::SolfeesFrontend))
+        }
+        "/api/solfees/sse" => Some((ReqType::Sse, SolanaRpcMode::SolfeesFrontend)),
+
+        path if path.starts_with("/v1/") && !is_upgrade => {
+            Some((ReqType::Rest, SolanaRpcMode::Solfees))
+        }
+
+        _ => None,
+    }
+}
+
+// JSON body, compressed if allowed by `Accept-Encoding`,
+// compression is executed on blocking pool to keep connection tasks responsive
+async fn compress_body(
+    mut response: http::response::Builder,
+    content_encoding: Option<ContentEncoding>,
+    compression_threshold: usize,
+    body: Vec<u8>,
+) -> (http::response::Builder, Bytes) {
+    response = response
+        .header(CONTENT_TYPE, "application/json; charset=utf-8")
+        .header(VARY, "accept-encoding");
+    let body = Bytes::from(body);
+    let Some(encoding) = content_encoding.filter(|_| body.len() >= compression_threshold) else {
+        return (response, body);
+    };
+
+    let data = body.clone();
+    match tokio::task::spawn_blocking(move || encoding.encode(&data)).await {
+        Ok(Ok(body)) => {
+            response = response.header(CONTENT_ENCODING, encoding.as_str());
+            (response, Bytes::from(body))
+        }
+        Ok(Err(error)) => {
+            error!(
+                %error,
+                encoding = encoding.as_str(),
+                "failed to compress response"
+            );
+            (response, body)
+        }
+        Err(error) => {
+            error!(
+                %error,
+                encoding = encoding.as_str(),
+                "compression task failed"
+            );
+            (response, body)
+        }
+    }
+}
+
+fn is_etag_matched(headers: &HeaderMap, etag: &str) -> bool {
+    headers.get_all(IF_NONE_MATCH).iter().any(|value| {
+        value.to_str().is_ok_and(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .any(|value| value == "*" || value == etag)
+        })
+    })
+}
+
+fn response_auth_error(error: AuthError) -> http::Result<Response<BoxBody<Bytes, Infallible>>> {
+    Response::builder()
+        .status(error.status())
+        .body(BodyFull::new(Bytes::from(error.as_str())).boxed())
+}
+
+fn response_request_error(
+    error: &RpcRequestError,
+) -> http::Result<Response<BoxBody<Bytes, Infallible>>> {
+    let mut response = Response::builder()
+        .status(error.status())
+        .header(CONTENT_TYPE, "application/json; charset=utf-8");
+    if matches!(error, RpcRequestError::QueueFull) {
+        response = response.header(RETRY_AFTER, "1");
+    }
+    response.body(BodyFull::new(Bytes::from(error.to_body())).boxed())
+}
+
+fn response_limit_exceeded(kind: LimitKind) -> http::Result<Response<BoxBody<Bytes, Infallible>>> {
+    Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .header(RETRY_AFTER, "1")
+        .body(BodyFull::new(Bytes::from(format!("{} limit exceeded", kind.as_str()))).boxed())
+}
+
+/// `/health` reports role if `leader` is set
+pub async fn run_admin(
+    addr: SocketAddr,
+    tls: Option<Arc<TlsAcceptor>>,
+    leader: Option<Arc<AtomicBool>>,
+    shutdown: Arc<Notify>,
+) -> anyhow::Result<()> {
+    let tls_enabled = tls.is_some();
+    let mut listener = Listener::bind(addr, tls).await?;
+    info!(%addr, tls = tls_enabled, "Start Admin RPC server");
 
     let http = ServerBuilder::new(TokioExecutor::new());
     let graceful = GracefulShutdown::new();
@@ -37,15 +331,26 @@ pub async fn run_admin(addr: SocketAddr, shutdown: Arc<Notify>) -> anyhow::Resul
             maybe_incoming = listener.accept() => maybe_incoming?,
         };
 
+        let leader = leader.clone();
         let connection = http.serve_connection(
-            TokioIo::new(Box::pin(stream)),
-            service_fn(move |req: Request<BodyIncoming>| async move {
-                let (status, body) = match req.uri().path() {
-                    "/health" => (StatusCode::OK, BodyFull::new(Bytes::from("ok")).boxed()),
-                    "/metrics" => (StatusCode::OK, metrics::collect_to_body()),
-                    _ => (StatusCode::NOT_FOUND, BodyEmpty::new().boxed()),
-                };
-                Response::builder().status(status).body(body)
+            TokioIo::new(stream),
+            service_fn(move |req: Request<BodyIncoming>| {
+                let leader = leader.clone();
+                async move {
+                    let (status, body) = match req.uri().path() {
+                        "/health" => {
+                            let body = match leader {
+                                Some(leader) if leader.load(Ordering::Relaxed) => "ok (active)",
+                                Some(_) => "ok (standby)",
+                                None => "ok",
+                            };
+                            (StatusCode::OK, BodyFull::new(Bytes::from(body)).boxed())
+                        }
+                        "/metrics" => (StatusCode::OK, metrics::collect_to_body()),
+                        _ => (StatusCode::NOT_FOUND, BodyEmpty::new().boxed()),
+                    };
+                    Response::builder().status(status).body(body)
+                }
             }),
         );
         let fut = graceful.watch(connection.into_owned());
@@ -64,14 +369,32 @@ pub async fn run_admin(addr: SocketAddr, shutdown: Arc<Notify>) -> anyhow::Resul
 }
 
 pub async fn run_solfees(
-    addr: SocketAddr,
-    body_limit: usize,
+    config: ConfigListenRpc,
     solana_rpc: SolanaRpc,
     config_metrics: Arc<ConfigMetrics>,
+    limits: Arc<Limits>,
+    api_keys: Arc<ApiKeys>,
+    tls: Option<Arc<TlsAcceptor>>,
     shutdown: Arc<Notify>,
 ) -> anyhow::Result<()> {
-    let listener = TcpListener::bind(addr).await?;
-    info!(%addr, "Start Solfees RPC server");
+    let addr = config.bind;
+    let body_limit = config.body_limit;
+    let compression_threshold = config.compression_threshold;
+    let cors = Arc::new(Cors::new(
+        config.cors,
+        [
+            config_metrics.usage_client_id.as_deref(),
+            config_metrics.usage_subscription_id.as_deref(),
+            api_keys.header(),
+            Some(LAST_EVENT_ID),
+        ]
+        .into_iter()
+        .flatten(),
+    )?);
+
+    let tls_enabled = tls.is_some();
+    let mut listener = Listener::bind(addr, tls).await?;
+    info!(%addr, tls = tls_enabled, "Start Solfees RPC server");
 
     let (ws_tx, _ws_rx) = broadcast::channel(1);
     let ws_tx = Arc::new(ws_tx);
@@ -79,106 +402,292 @@ pub async fn run_solfees(
     let http = ServerBuilder::new(TokioExecutor::new());
     let graceful = GracefulShutdown::new();
     loop {
-        let (stream, _addr) = tokio::select! {
+        let (stream, remote_addr) = tokio::select! {
             () = shutdown.notified() => break,
             maybe_incoming = listener.accept() => maybe_incoming?,
         };
 
         let solana_rpc = solana_rpc.clone();
         let config_metrics = Arc::clone(&config_metrics);
+        let limits = Arc::clone(&limits);
+        let api_keys = Arc::clone(&api_keys);
+        let cors = Arc::clone(&cors);
         let ws_tx = Arc::clone(&ws_tx);
         let connection = http.serve_connection_with_upgrades(
-            TokioIo::new(Box::pin(stream)),
+            TokioIo::new(stream),
             service_fn(move |mut req: Request<BodyIncoming>| {
                 let solana_rpc = solana_rpc.clone();
                 let config_metrics = Arc::clone(&config_metrics);
+                let limits = Arc::clone(&limits);
                 let ws_tx = Arc::clone(&ws_tx);
+                let api_keys = Arc::clone(&api_keys);
+                let cors = Arc::clone(&cors);
                 async move {
-                    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-                    enum ReqType {
-                        Rpc,
-                        WebSocket,
-                    }
+                    let allow_origin = cors.get_allow_origin(req.headers());
+                    let mut response = async {
+                        // API key can be passed as first path segment: `/{key}/api/...`
+                        let is_upgrade = is_upgrade_request(&req);
+                        let path = req.uri().path();
+                        let route = match parse_route(path, is_upgrade) {
+                            Some(route) => Some((route, None)),
+                            None => api_keys.split_path(path).and_then(|(key, path)| {
+                                parse_route(path, is_upgrade).map(|route| (route, Some(key)))
+                            }),
+                        };
+                        let Some(((req_type, solana_rpc_mode), path_key)) = route else {
+                            return Response::builder()
+                                .status(StatusCode::NOT_FOUND)
+                                .body(BodyEmpty::new().boxed());
+                        };
 
-                    let (req_type, solana_rpc_mode) = match req.uri().path() {
-                        "/api/solana" => (ReqType::Rpc, SolanaRpcMode::Solana),
-                        "/api/solana/triton" => (ReqType::Rpc, SolanaRpcMode::Triton),
-                        "/api/solana/solfees" => (ReqType::Rpc, SolanaRpcMode::Solfees),
-                        "/api/solana/solfees/ws" if is_upgrade_request(&req) => {
-                            (ReqType::WebSocket, SolanaRpcMode::Solfees)
-                        }
+                        let path_offset = path_key.map_or(0, |key| key.len() + 1);
 
-                        "/api/solfees" => (ReqType::Rpc, SolanaRpcMode::SolfeesFrontend),
-                        "/api/solfees/ws" if is_upgrade_request(&req) => {
-                            (ReqType::WebSocket, SolanaRpcMode::SolfeesFrontend)
-                        }
+                        if req.method() == Method::OPTIONS && req_type != ReqType::WebSocket {
+                            if allow_origin.is_none() {
+                                return Response::builder()
+                                    .status(StatusCode::FORBIDDEN)
+                                    .body(BodyEmpty::new().boxed());
+                            }
 
-                        _ => {
-                            return Response::builder()
-                                .status(StatusCode::NOT_FOUND)
-                                .body(BodyEmpty::new().boxed())
+                            let mut response = Response::builder()
+                                .status(StatusCode::NO_CONTENT)
+                                .body(BodyEmpty::new().boxed());
+                            if let Ok(response) = response.as_mut() {
+                                cors.set_preflight_headers(response.headers_mut());
+                            }
+                            return response;
                         }
-                    };
 
-                    let client_id = metrics_be::ClientId::new(req.headers(), &config_metrics);
-                    match req_type {
-                        ReqType::Rpc => {
-                            let ts = Instant::now();
-                            let response = match Limited::new(req.into_body(), body_limit)
-                                .collect()
-                                .map_err(|error| anyhow::anyhow!(error))
-                                .and_then(|body| {
-                                    solana_rpc.on_request(
-                                        client_id,
+                        let api_key = match api_keys.authorize(
+                            path_key,
+                            req.headers(),
+                            req.uri().query(),
+                            solana_rpc_mode,
+                        ) {
+                            Ok(api_key) => api_key,
+                            Err(error) => return response_auth_error(error),
+                        };
+
+                        let client_id = metrics_be::ClientId::new(
+                            req.headers(),
+                            remote_addr.ip(),
+                            &config_metrics,
+                            api_key.as_deref(),
+                        );
+                        match req_type {
+                            ReqType::Rpc => {
+                                let ts = Instant::now();
+                                if let Err(kind) = limits.check_request(&client_id) {
+                                    let response = response_limit_exceeded(kind);
+                                    metrics_be::requests_observe(
                                         solana_rpc_mode,
-                                        body.aggregate(),
-                                    )
-                                })
-                                .await
-                            {
-                                Ok((stats, body)) => {
-                                    metrics_be::requests_call_inc(solana_rpc_mode, stats);
-                                    Response::builder()
-                                        .header(CONTENT_TYPE, "application/json; charset=utf-8")
-                                        .header(ACCESS_CONTROL_ALLOW_METHODS, "OPTIONS, POST")
-                                        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-                                        .body(BodyFull::new(Bytes::from(body)).boxed())
+                                        response.as_ref().map(|resp| resp.status()).ok(),
+                                        ts.elapsed(),
+                                    );
+                                    return response;
                                 }
-                                Err(error) => Response::builder()
-                                    .status(StatusCode::INTERNAL_SERVER_ERROR)
-                                    .body(BodyFull::new(Bytes::from(format!("{error}"))).boxed()),
-                            };
-                            metrics_be::requests_observe(
-                                solana_rpc_mode,
-                                response.as_ref().map(|resp| resp.status()).ok(),
-                                ts.elapsed(),
-                            );
-                            response
-                        }
-                        ReqType::WebSocket => {
-                            match hyper_tungstenite::upgrade(
-                                &mut req,
-                                Some(WebSocketConfig {
-                                    max_message_size: Some(body_limit), // max incoming message size
-                                    ..Default::default()
-                                }),
-                            ) {
-                                Ok((response, websocket)) => {
-                                    tokio::spawn(solana_rpc.on_websocket(
-                                        client_id,
+
+                                let content_encoding = ContentEncoding::from_headers(req.headers());
+                                let response = match Limited::new(req.into_body(), body_limit)
+                                    .collect()
+                                    .map_err(|error| {
+                                        if error.is::<LengthLimitError>() {
+                                            RpcRequestError::BodyLimit
+                                        } else {
+                                            RpcRequestError::Body(error.to_string())
+                                        }
+                                    })
+                                    .and_then(|body| {
+                                        solana_rpc.on_request(
+                                            client_id.clone(),
+                                            solana_rpc_mode,
+                                            &limits,
+                                            body.aggregate(),
+                                        )
+                                    })
+                                    .await
+                                {
+                                    Ok((stats, body)) => {
+                                        metrics_be::requests_call_inc(solana_rpc_mode, stats);
+                                        let (response, body) = compress_body(
+                                            Response::builder(),
+                                            content_encoding,
+                                            compression_threshold,
+                                            body,
+                                        )
+                                        .await;
+                                        // response is sent anyway, see `Limits::consume_egress`
+                                        let _ = limits.consume_egress(&client_id, body.len());
+                                        response.body(BodyFull::new(body).boxed())
+                                    }
+                                    Err(error) => response_request_error(&error),
+                                };
+                                metrics_be::requests_observe(
+                                    solana_rpc_mode,
+                                    response.as_ref().map(|resp| resp.status()).ok(),
+                                    ts.elapsed(),
+                                );
+                                response
+                            }
+                            ReqType::Rest => {
+                                let ts = Instant::now();
+                                if req.method() != Method::GET && req.method() != Method::HEAD {
+                                    return Response::builder()
+                                        .status(StatusCode::METHOD_NOT_ALLOWED)
+                                        .header(ALLOW, "GET, HEAD, OPTIONS")
+                                        .body(BodyEmpty::new().boxed());
+                                }
+
+                                if let Err(kind) = limits.check_request(&client_id) {
+                                    let response = response_limit_exceeded(kind);
+                                    metrics_be::requests_observe(
                                         solana_rpc_mode,
-                                        websocket,
-                                        ws_tx.subscribe(),
-                                    ));
-                                    let (parts, body) = response.into_parts();
-                                    Ok(Response::from_parts(parts, body.boxed()))
+                                        response.as_ref().map(|resp| resp.status()).ok(),
+                                        ts.elapsed(),
+                                    );
+                                    return response;
+                                }
+
+                                let content_encoding = ContentEncoding::from_headers(req.headers());
+                                let response = match solana_rpc
+                                    .on_rest(
+                                        client_id.clone(),
+                                        &req.uri().path()[path_offset..],
+                                        req.uri().query(),
+                                    )
+                                    .await
+                                {
+                                    Ok((stats, body, version)) => {
+                                        metrics_be::requests_call_inc(solana_rpc_mode, stats);
+                                        let etag = version.etag();
+                                        let response = Response::builder()
+                                            .header(CACHE_CONTROL, "no-cache")
+                                            .header(ETAG, &etag);
+                                        if is_etag_matched(req.headers(), &etag) {
+                                            response
+                                                .status(StatusCode::NOT_MODIFIED)
+                                                .body(BodyEmpty::new().boxed())
+                                        } else {
+                                            let (response, body) = compress_body(
+                                                response,
+                                                content_encoding,
+                                                compression_threshold,
+                                                body,
+                                            )
+                                            .await;
+                                            // response is sent anyway, see `Limits::consume_egress`
+                                            let _ = limits.consume_egress(&client_id, body.len());
+                                            response.body(BodyFull::new(body).boxed())
+                                        }
+                                    }
+                                    Err(error) => response_request_error(&error),
+                                };
+                                metrics_be::requests_observe(
+                                    solana_rpc_mode,
+                                    response.as_ref().map(|resp| resp.status()).ok(),
+                                    ts.elapsed(),
+                                );
+                                response
+                            }
+                            ReqType::Sse => {
+                                if req.method() != Method::GET {
+                                    return Response::builder()
+                                        .status(StatusCode::METHOD_NOT_ALLOWED)
+                                        .header(ALLOW, "GET, OPTIONS")
+                                        .body(BodyEmpty::new().boxed());
+                                }
+
+                                let last_event_id = req
+                                    .headers()
+                                    .get(LAST_EVENT_ID)
+                                    .and_then(|value| value.to_str().ok())
+                                    .and_then(|value| value.parse().ok());
+                                let subscription = match solana_rpc.sse_subscribe(
+                                    solana_rpc_mode,
+                                    req.uri().query(),
+                                    last_event_id,
+                                ) {
+                                    Ok(subscription) => subscription,
+                                    Err(error) => return response_request_error(&error),
+                                };
+
+                                let permit = match limits.acquire_websocket(&client_id) {
+                                    Ok(permit) => permit,
+                                    Err(kind) => return response_limit_exceeded(kind),
+                                };
+
+                                let (events_tx, events_rx) = futures_mpsc::channel(16);
+                                tokio::spawn(subscription.run(
+                                    client_id,
+                                    events_tx,
+                                    ws_tx.subscribe(),
+                                    limits,
+                                    permit,
+                                ));
+                                Response::builder()
+                                    .header(CONTENT_TYPE, "text/event-stream")
+                                    .header(CACHE_CONTROL, "no-cache")
+                                    .header("x-accel-buffering", "no") // disable nginx buffering
+                                    .body(
+                                        StreamBody::new(
+                                            events_rx.map(|event| Ok(Frame::data(event))),
+                                        )
+                                        .boxed(),
+                                    )
+                            }
+                            ReqType::WebSocket => {
+                                let compression = match WebSocketCompression::from_query(
+                                    req.uri().query(),
+                                    compression_threshold,
+                                ) {
+                                    Ok(compression) => compression,
+                                    Err(error) => {
+                                        return Response::builder()
+                                            .status(StatusCode::BAD_REQUEST)
+                                            .body(BodyFull::new(Bytes::from(error)).boxed())
+                                    }
+                                };
+
+                                let permit = match limits.acquire_websocket(&client_id) {
+                                    Ok(permit) => permit,
+                                    Err(kind) => return response_limit_exceeded(kind),
+                                };
+
+                                match hyper_tungstenite::upgrade(
+                                    &mut req,
+                                    Some(WebSocketConfig {
+                                        max_message_size: Some(body_limit), // max incoming message size
+                                        ..Default::default()
+                                    }),
+                                ) {
+                                    Ok((response, websocket)) => {
+                                        tokio::spawn(solana_rpc.on_websocket(
+                                            client_id,
+                                            solana_rpc_mode,
+                                            websocket,
+                                            compression,
+                                            ws_tx.subscribe(),
+                                            limits,
+                                            permit,
+                                        ));
+                                        let (parts, body) = response.into_parts();
+                                        Ok(Response::from_parts(parts, body.boxed()))
+                                    }
+                                    Err(error) => {
+                                        Response::builder().status(StatusCode::BAD_REQUEST).body(
+                                            BodyFull::new(Bytes::from(format!("{error:?}")))
+                                                .boxed(),
+                                        )
+                                    }
                                 }
-                                Err(error) => Response::builder()
-                                    .status(StatusCode::BAD_REQUEST)
-                                    .body(BodyFull::new(Bytes::from(format!("{error:?}"))).boxed()),
                             }
                         }
                     }
+                    .await;
+                    if let Ok(response) = response.as_mut() {
+                        cors.set_headers(allow_origin, response.headers_mut());
+                    }
+                    response
                 }
             }),
         );
@@ -1,48 +1,67 @@
 use {
     crate::{
-        grpc_geyser::{CommitmentLevel, GeyserMessage, GeyserTransaction},
+        bus::BusMessage,
+        grpc_geyser::{CommitmentLevel, GeyserMessage, GeyserTransaction, SlotSummary},
+        limits::{LimitKind, Limits, LimitsWebSocketPermit},
         metrics::solfees_be::{self as metrics, ClientId},
-        redis::RedisMessage,
+        rpc_server::WebSocketCompression,
     },
+    arc_swap::ArcSwap,
+    base64::{prelude::BASE64_STANDARD, Engine},
+    bincode::Options,
+    dashmap::DashMap,
     futures::{
-        future::{join_all, pending, BoxFuture, FutureExt},
+        channel::mpsc as futures_mpsc,
+        future::{pending, BoxFuture, FutureExt},
         sink::SinkExt,
         stream::StreamExt,
     },
-    hyper::body::Buf,
+    hyper::{
+        body::{Buf, Bytes},
+        StatusCode,
+    },
     hyper_tungstenite::HyperWebsocket,
     jsonrpc_core::{
-        Call as JsonrpcCall, Error as JsonrpcError, Failure as JsonrpcFailure, Id as JsonrpcId,
-        MethodCall as JsonrpcMethodCall, Value as JsonrcpValue, Version as JsonrpcVersion,
+        Call as JsonrpcCall, Error as JsonrpcError, ErrorCode as JsonrpcErrorCode,
+        Failure as JsonrpcFailure, Id as JsonrpcId, MethodCall as JsonrpcMethodCall,
+        Value as JsonrcpValue, Version as JsonrpcVersion,
     },
-    serde::{Deserialize, Serialize},
+    serde::{de::DeserializeOwned, Deserialize, Serialize, Serializer},
+    serde_json::value::{to_raw_value, RawValue},
     solana_rpc_client_api::{
         config::{RpcContextConfig, RpcLeaderScheduleConfig, RpcLeaderScheduleConfigWrapper},
         custom_error::RpcCustomError,
+        request::MAX_GET_CONFIRMED_BLOCKS_RANGE,
         response::{
             Response as RpcResponse, RpcBlockhash, RpcPrioritizationFee, RpcResponseContext,
             RpcVersionInfo,
         },
     },
     solana_sdk::{
-        clock::{Epoch, Slot, UnixTimestamp, MAX_PROCESSING_AGE},
+        clock::{Epoch, Slot, UnixTimestamp, DEFAULT_MS_PER_SLOT, MAX_PROCESSING_AGE},
+        commitment_config::CommitmentConfig,
         epoch_schedule::EpochSchedule,
         hash::Hash,
+        message::{AccountKeys, VersionedMessage},
+        packet::PACKET_DATA_SIZE,
         pubkey::Pubkey,
         transaction::MAX_TX_ACCOUNT_LOCKS,
     },
     std::{
         borrow::Cow,
-        collections::{BTreeMap, BTreeSet, HashMap},
+        collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+        fmt,
+        ops::Bound,
+        str::FromStr,
         sync::{
             atomic::{AtomicBool, Ordering},
             Arc,
         },
-        time::Duration,
+        time::{Duration, SystemTime, UNIX_EPOCH},
     },
     tokio::{
-        sync::{broadcast, mpsc, oneshot, Mutex},
-        time::sleep,
+        sync::{broadcast, mpsc, oneshot, watch},
+        time::{interval_at, sleep, Instant, MissedTickBehavior},
     },
     tokio_tungstenite::tungstenite::protocol::{
         frame::{coding::CloseCode as WebSocketCloseCode, CloseFrame as WebSocketCloseFrame},
@@ -51,7 +70,16 @@ use {
     tracing::{debug, info},
 };
 
+const BUS_CHANNEL_SIZE: usize = 2_048;
 const MAX_NUM_RECENT_SLOT_INFO: usize = 150;
+const MAX_NUM_CACHED_RESPONSES: usize = 16_384;
+const SSE_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+lazy_static::lazy_static! {
+    static ref EPOCH_SCHEDULE: EpochSchedule = EpochSchedule::custom(432_000, 432_000, false);
+}
 
 #[derive(Debug, Serialize)]
 #[serde(deny_unknown_fields)]
@@ -78,6 +106,25 @@ enum JsonrcpValueArced {
     RpcPrioritizationFee(Vec<RpcPrioritizationFee>),
     SolfeesSlotsFrontend(Vec<SlotsSubscribeOutput>),
     SolfeesSlots(Vec<SolfeesPrioritizationFee>),
+    Cached(JsonrpcValueCached),
+}
+
+impl From<JsonrpcValueCached> for JsonrcpValueArced {
+    fn from(value: JsonrpcValueCached) -> Self {
+        Self::Cached(value)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct JsonrpcValueCached(Arc<RawValue>);
+
+impl Serialize for JsonrpcValueCached {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.as_ref().serialize(serializer)
+    }
 }
 
 impl From<Option<&Arc<JsonrcpValue>>> for JsonrcpValueArced {
@@ -104,11 +151,13 @@ impl From<Vec<SolfeesPrioritizationFee>> for JsonrcpValueArced {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SolanaRpcMode {
     Solana,
     Triton,
     Solfees,
+    #[serde(rename = "frontend")]
     SolfeesFrontend,
 }
 
@@ -123,52 +172,137 @@ impl SolanaRpcMode {
     }
 }
 
+#[derive(Debug)]
+pub enum RpcRequestError {
+    BodyLimit,
+    Body(String),
+    Parse(serde_json::Error),
+    CallsLimit(usize),
+    BatchCallsLimit(usize),
+    NotFound,
+    Rpc(JsonrpcError),
+    QueueFull,
+    Shutdown,
+    Timeout,
+    Internal(anyhow::Error),
+}
+
+impl fmt::Display for RpcRequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BodyLimit => write!(f, "request body is too large"),
+            Self::Body(error) => write!(f, "failed to read request body: {error}"),
+            Self::Parse(error) => write!(f, "failed to parse request: {error}"),
+            Self::CallsLimit(max) => {
+                write!(f, "exceed allowed weight of calls in one request ({max})")
+            }
+            Self::BatchCallsLimit(max) => {
+                write!(f, "exceed allowed number of calls in one request ({max})")
+            }
+            Self::NotFound => write!(f, "not found"),
+            Self::Rpc(error) => write!(f, "{}", error.message),
+            Self::QueueFull => write!(f, "requests queue is full"),
+            Self::Shutdown => write!(f, "server is shutting down"),
+            Self::Timeout => write!(f, "request timeout"),
+            Self::Internal(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl RpcRequestError {
+    pub const fn status(&self) -> StatusCode {
+        match self {
+            Self::BodyLimit => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Body(_) | Self::Parse(_) | Self::CallsLimit(_) | Self::BatchCallsLimit(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Rpc(JsonrpcError {
+                code: JsonrpcErrorCode::InternalError,
+                ..
+            }) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Rpc(_) => StatusCode::BAD_REQUEST,
+            Self::QueueFull => StatusCode::TOO_MANY_REQUESTS,
+            Self::Shutdown => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// JSON-RPC error object with `null` id
+    pub fn to_body(&self) -> Vec<u8> {
+        let error = match self {
+            Self::Rpc(error) => error.clone(),
+            Self::Parse(_) => JsonrpcError::parse_error(),
+            Self::BodyLimit
+            | Self::Body(_)
+            | Self::CallsLimit(_)
+            | Self::BatchCallsLimit(_)
+            | Self::NotFound => JsonrpcError {
+                code: JsonrpcErrorCode::InvalidRequest,
+                message: self.to_string(),
+                data: None,
+            },
+            Self::QueueFull | Self::Shutdown | Self::Timeout | Self::Internal(_) => JsonrpcError {
+                code: JsonrpcErrorCode::InternalError,
+                message: self.to_string(),
+                data: None,
+            },
+        };
+        let output = SolanaRpc::create_failure(Some(JsonrpcVersion::V2), JsonrpcId::Null, error);
+        let mut body = serde_json::to_vec(&output).expect("failed to serialize");
+        body.push(b'\n');
+        body
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SolanaRpc {
     request_calls_max: usize,
+    request_calls_weights: Arc<HashMap<String, usize>>,
     request_timeout: Duration,
-    redis_tx: broadcast::Sender<RedisMessage>,
-    requests_tx: mpsc::Sender<RpcRequestTask>,
+    bus_tx: mpsc::Sender<BusMessage>,
+    requests_tx: async_channel::Sender<RpcRequestTask>,
     streams_tx: broadcast::Sender<Arc<StreamsUpdateMessage>>,
+    position_rx: watch::Receiver<Option<String>>,
+    state: Arc<ArcSwap<SolanaRpcState>>,
 }
 
 impl SolanaRpc {
     pub fn new(
         request_calls_max: usize,
+        request_calls_weights: HashMap<String, usize>,
         request_timeout: Duration,
         calls_queue_max: usize,
         streams_channel_capacity: usize,
         pool_size: usize,
     ) -> (Self, Vec<BoxFuture<'static, anyhow::Result<()>>>) {
-        const REDIS_CHANNEL_SIZE: usize = 2_048;
-
-        let (redis_tx, _redis_rx) = broadcast::channel(REDIS_CHANNEL_SIZE);
+        let (bus_tx, bus_rx) = mpsc::channel(BUS_CHANNEL_SIZE);
         let (streams_tx, _streams_rx) = broadcast::channel(streams_channel_capacity);
-        let (requests_tx, requests_rx) = mpsc::channel(calls_queue_max);
+        let (requests_tx, requests_rx) = async_channel::bounded(calls_queue_max);
+        let (position_tx, position_rx) = watch::channel(None);
 
-        let requests_rx = Arc::new(Mutex::new(requests_rx));
+        let state = Arc::new(ArcSwap::from_pointee(SolanaRpcState::default()));
 
         let rpc = Self {
             request_calls_max,
+            request_calls_weights: Arc::new(request_calls_weights),
             request_timeout,
-            redis_tx: redis_tx.clone(),
+            bus_tx,
             requests_tx,
             streams_tx: streams_tx.clone(),
+            position_rx,
+            state: Arc::clone(&state),
         };
 
         let mut futs = vec![
-            // WebSocket source
-            Self::run_subscribe_update_loop(redis_tx.subscribe(), streams_tx).boxed(),
+            // state and WebSocket source
+            Self::run_update_loop(bus_rx, Arc::clone(&state), streams_tx, position_tx).boxed(),
         ];
-        for (index, ()) in std::iter::repeat(()).take(pool_size).enumerate() {
+        for _ in 0..pool_size {
             futs.push(
                 // RPC handler
-                Self::run_request_update_loop(
-                    index,
-                    redis_tx.subscribe(),
-                    Arc::clone(&requests_rx),
-                )
-                .boxed(),
+                Self::run_request_loop(requests_rx.clone(), Arc::clone(&state)).boxed(),
             );
         }
 
@@ -176,12 +310,17 @@ impl SolanaRpc {
     }
 
     pub fn shutdown(self) {
-        drop(self.redis_tx)
+        self.requests_tx.close();
     }
 
-    pub fn push_redis_message(&self, message: RedisMessage) -> anyhow::Result<()> {
+    /// Last `BusMessage::Position`, updated when all messages before it are applied
+    pub fn subscribe_position(&self) -> watch::Receiver<Option<String>> {
+        self.position_rx.clone()
+    }
+
+    pub async fn push_bus_message(&self, message: BusMessage) -> anyhow::Result<()> {
         anyhow::ensure!(
-            self.redis_tx.send(message).is_ok(),
+            self.bus_tx.send(message).await.is_ok(),
             "SolanaRpc update loop is dead"
         );
         Ok(())
@@ -191,8 +330,9 @@ impl SolanaRpc {
         &self,
         client_id: ClientId,
         mode: SolanaRpcMode,
+        limits: &Limits,
         body: impl Buf,
-    ) -> anyhow::Result<(RpcRequestsStats, Vec<u8>)> {
+    ) -> Result<(RpcRequestsStats, Vec<u8>), RpcRequestError> {
         let timer = client_id.start_timer_cpu();
         let mut stats = RpcRequestsStats::default();
 
@@ -203,20 +343,28 @@ impl SolanaRpc {
             Batch(Vec<JsonrpcCall>),
         }
 
-        let (batched_calls, calls) = match serde_json::from_reader(body.reader())? {
-            JsonrpcCalls::Single(call) => (false, vec![call]),
-            JsonrpcCalls::Batch(calls) => (true, calls),
-        };
+        let (batched_calls, calls) =
+            match serde_json::from_reader(body.reader()).map_err(RpcRequestError::Parse)? {
+                JsonrpcCalls::Single(call) => (false, vec![call]),
+                JsonrpcCalls::Batch(calls) => (true, calls),
+            };
         let calls_total = calls.len();
-        anyhow::ensure!(
-            calls_total <= self.request_calls_max,
-            "exceed number of allowed calls in one request ({})",
-            self.request_calls_max
-        );
+        let calls_weight = calls
+            .iter()
+            .map(|call| self.get_call_weight(call))
+            .fold(0usize, usize::saturating_add);
+        if calls_weight > self.request_calls_max {
+            return Err(RpcRequestError::CallsLimit(self.request_calls_max));
+        }
+
+        limits
+            .check_batch_calls(&client_id, calls_total)
+            .map_err(RpcRequestError::BatchCallsLimit)?;
+        let calls_allowed = limits.check_calls(&client_id, calls_total);
 
         let mut outputs = Vec::with_capacity(calls_total);
         let mut requests = Vec::with_capacity(calls_total);
-        for call in calls {
+        for (index, call) in calls.into_iter().enumerate() {
             let call = match call {
                 JsonrpcCall::MethodCall(call) => call,
                 JsonrpcCall::Notification(notification) => {
@@ -237,7 +385,161 @@ impl SolanaRpc {
                 }
             };
 
+            if index >= calls_allowed {
+                outputs.push(Some(Self::create_failure(
+                    call.jsonrpc,
+                    call.id,
+                    Self::limit_exceeded_error(LimitKind::Calls),
+                )));
+                continue;
+            }
+
             match call.method.as_str() {
+                "getBlockHeight" if mode != SolanaRpcMode::SolfeesFrontend => {
+                    stats.block_height += 1;
+
+                    #[derive(Debug, Deserialize)]
+                    struct ReqParams {
+                        #[serde(default)]
+                        config: Option<RpcContextConfig>,
+                    }
+
+                    outputs.push(
+                        match call.params.parse().map(|ReqParams { config }| {
+                            let RpcContextConfig {
+                                commitment,
+                                min_context_slot,
+                            } = config.unwrap_or_default();
+                            (commitment, min_context_slot)
+                        }) {
+                            Ok((commitment, min_context_slot)) => {
+                                requests.push(RpcRequest::BlockHeight {
+                                    jsonrpc: call.jsonrpc,
+                                    id: call.id,
+                                    commitment: commitment.unwrap_or_default().into(),
+                                    min_context_slot,
+                                });
+                                None
+                            }
+                            Err(error) => Some(Self::create_failure(call.jsonrpc, call.id, error)),
+                        },
+                    )
+                }
+                "getBlockTime" if mode != SolanaRpcMode::SolfeesFrontend => {
+                    stats.block_time += 1;
+
+                    #[derive(Debug, Deserialize)]
+                    struct ReqParams {
+                        slot: Slot,
+                    }
+
+                    outputs.push(match call.params.parse() {
+                        Ok(ReqParams { slot }) => {
+                            requests.push(RpcRequest::BlockTime {
+                                jsonrpc: call.jsonrpc,
+                                id: call.id,
+                                slot,
+                            });
+                            None
+                        }
+                        Err(error) => Some(Self::create_failure(call.jsonrpc, call.id, error)),
+                    })
+                }
+                "getBlocks" if mode != SolanaRpcMode::SolfeesFrontend => {
+                    stats.blocks += 1;
+
+                    #[derive(Debug, Deserialize)]
+                    #[serde(untagged)]
+                    enum EndSlotOrConfig {
+                        EndSlot(Slot),
+                        Config(RpcContextConfig),
+                    }
+
+                    #[derive(Debug, Deserialize)]
+                    struct ReqParams {
+                        start_slot: Slot,
+                        #[serde(default)]
+                        wrapper: Option<EndSlotOrConfig>,
+                        #[serde(default)]
+                        config: Option<RpcContextConfig>,
+                    }
+
+                    outputs.push(
+                        match call.params.parse().and_then(
+                            |ReqParams {
+                                 start_slot,
+                                 wrapper,
+                                 config,
+                             }| {
+                                let (end_slot, config) = match wrapper {
+                                    Some(EndSlotOrConfig::EndSlot(end_slot)) => {
+                                        (Some(end_slot), config)
+                                    }
+                                    Some(EndSlotOrConfig::Config(config)) => (None, Some(config)),
+                                    None => (None, config),
+                                };
+                                let commitment = config
+                                    .and_then(|config| config.commitment)
+                                    .unwrap_or_default();
+                                if !commitment.is_at_least_confirmed() {
+                                    return Err(JsonrpcError::invalid_params(
+                                        "Method does not support commitment below `confirmed`",
+                                    ));
+                                }
+                                Ok((start_slot, end_slot, commitment))
+                            },
+                        ) {
+                            Ok((start_slot, end_slot, commitment)) => {
+                                requests.push(RpcRequest::Blocks {
+                                    jsonrpc: call.jsonrpc,
+                                    id: call.id,
+                                    start_slot,
+                                    end_slot,
+                                    commitment: commitment.into(),
+                                });
+                                None
+                            }
+                            Err(error) => Some(Self::create_failure(call.jsonrpc, call.id, error)),
+                        },
+                    )
+                }
+                "getFeeForMessage" if mode != SolanaRpcMode::SolfeesFrontend => {
+                    stats.fee_for_message += 1;
+
+                    #[derive(Debug, Deserialize)]
+                    struct ReqParams {
+                        message: String,
+                        #[serde(default)]
+                        config: Option<RpcContextConfig>,
+                    }
+
+                    outputs.push(
+                        match call
+                            .params
+                            .parse()
+                            .and_then(|ReqParams { message, config }| {
+                                let (blockhash, fee) = decode_message_fee(&message)?;
+                                let RpcContextConfig {
+                                    commitment,
+                                    min_context_slot,
+                                } = config.unwrap_or_default();
+                                Ok((blockhash, fee, commitment, min_context_slot))
+                            }) {
+                            Ok((blockhash, fee, commitment, min_context_slot)) => {
+                                requests.push(RpcRequest::FeeForMessage {
+                                    jsonrpc: call.jsonrpc,
+                                    id: call.id,
+                                    blockhash,
+                                    fee,
+                                    commitment: commitment.unwrap_or_default().into(),
+                                    min_context_slot,
+                                });
+                                None
+                            }
+                            Err(error) => Some(Self::create_failure(call.jsonrpc, call.id, error)),
+                        },
+                    )
+                }
                 "getLatestBlockhash" if mode != SolanaRpcMode::SolfeesFrontend => {
                     stats.latest_blockhash += 1;
 
@@ -254,7 +556,11 @@ impl SolanaRpc {
                                     commitment,
                                     min_context_slot,
                                 } = config.unwrap_or_default();
-                                (commitment, 0, min_context_slot)
+                                (
+                                    commitment,
+                                    LatestBlockhashRollback::Slots(0),
+                                    min_context_slot,
+                                )
                             })
                         }
                         SolanaRpcMode::Triton | SolanaRpcMode::Solfees => {
@@ -264,10 +570,19 @@ impl SolanaRpc {
                                 config: Option<RpcLatestBlockhashConfigTriton>,
                             }
 
-                            call.params.parse().map(|ReqParams { config }| {
-                                let RpcLatestBlockhashConfigTriton { context, rollback } =
-                                    config.unwrap_or_default();
-                                (context.commitment, rollback, context.min_context_slot)
+                            call.params.parse().and_then(|ReqParams { config }| {
+                                let RpcLatestBlockhashConfigTriton {
+                                    context,
+                                    rollback,
+                                    rollback_ms,
+                                    remaining_validity,
+                                } = config.unwrap_or_default();
+                                let rollback = LatestBlockhashRollback::new(
+                                    rollback,
+                                    rollback_ms,
+                                    remaining_validity,
+                                )?;
+                                Ok((context.commitment, rollback, context.min_context_slot))
                             })
                         }
                         SolanaRpcMode::SolfeesFrontend => unreachable!(),
@@ -278,6 +593,7 @@ impl SolanaRpc {
                             requests.push(RpcRequest::LatestBlockhash {
                                 jsonrpc: call.jsonrpc,
                                 id: call.id,
+                                mode,
                                 commitment: commitment.unwrap_or_default().into(),
                                 rollback,
                                 min_context_slot,
@@ -439,6 +755,7 @@ impl SolanaRpc {
                                 requests.push(RpcRequest::RecentPrioritizationFees {
                                     jsonrpc: call.jsonrpc,
                                     id: call.id,
+                                    mode,
                                     pubkeys,
                                     percentile,
                                 });
@@ -495,6 +812,42 @@ impl SolanaRpc {
                         )
                     }));
                 }
+                "isBlockhashValid" if mode != SolanaRpcMode::SolfeesFrontend => {
+                    stats.is_blockhash_valid += 1;
+
+                    #[derive(Debug, Deserialize)]
+                    struct ReqParams {
+                        blockhash: String,
+                        #[serde(default)]
+                        config: Option<RpcContextConfig>,
+                    }
+
+                    outputs.push(
+                        match call
+                            .params
+                            .parse()
+                            .and_then(|ReqParams { blockhash, config }| {
+                                let blockhash = verify_hash(&blockhash)?;
+                                let RpcContextConfig {
+                                    commitment,
+                                    min_context_slot,
+                                } = config.unwrap_or_default();
+                                Ok((blockhash, commitment, min_context_slot))
+                            }) {
+                            Ok((blockhash, commitment, min_context_slot)) => {
+                                requests.push(RpcRequest::IsBlockhashValid {
+                                    jsonrpc: call.jsonrpc,
+                                    id: call.id,
+                                    blockhash,
+                                    commitment: commitment.unwrap_or_default().into(),
+                                    min_context_slot,
+                                });
+                                None
+                            }
+                            Err(error) => Some(Self::create_failure(call.jsonrpc, call.id, error)),
+                        },
+                    )
+                }
                 _ => {
                     outputs.push(Some(Self::create_failure(
                         call.jsonrpc,
@@ -507,51 +860,26 @@ impl SolanaRpc {
         timer.stop_and_record();
 
         if !requests.is_empty() {
-            let shutdown = Arc::new(AtomicBool::new(false));
-
-            let mut rxs = Vec::with_capacity(requests.len());
-            for request in requests {
-                let (tx, rx) = oneshot::channel();
-                match self.requests_tx.try_send(RpcRequestTask {
-                    client_id: client_id.clone(),
-                    request,
-                    shutdown: Arc::clone(&shutdown),
-                    tx,
-                }) {
-                    Ok(()) => {}
-                    Err(mpsc::error::TrySendError::Full(_)) => {
-                        shutdown.store(true, Ordering::Relaxed);
-                        anyhow::bail!("requests queue is full");
-                    }
-                    Err(mpsc::error::TrySendError::Closed(_)) => {
-                        anyhow::bail!("processed loop is closed");
-                    }
+            let mut index = 0;
+            let (outputs_processed, _version) = self.process_requests(&client_id, requests).await?;
+            for output in outputs_processed {
+                while index < outputs.len() && outputs[index].is_some() {
+                    index += 1;
                 }
-                metrics::requests_queue_size_inc();
-                rxs.push(rx);
-            }
-            let response_rx = join_all(rxs);
-
-            tokio::select! {
-                () = sleep(self.request_timeout) => {
-                    shutdown.store(true, Ordering::Relaxed);
-                    anyhow::bail!("request timeout");
-                },
-                maybe_outputs = response_rx => {
-                    let mut index = 0;
-                    for output in maybe_outputs {
-                        while index < outputs.len() && outputs[index].is_some() {
-                            index += 1;
-                        }
-                        anyhow::ensure!(index < outputs.len(), "output index out of bounds");
-
-                        outputs[index] = Some(output.map_err(|_error| anyhow::anyhow!("request dropped"))?);
-                    }
+                if index >= outputs.len() {
+                    return Err(RpcRequestError::Internal(anyhow::anyhow!(
+                        "output index out of bounds"
+                    )));
                 }
+                outputs[index] = Some(output);
             }
         }
 
-        anyhow::ensure!(calls_total == outputs.len(), "invalid number of outputs");
+        if calls_total != outputs.len() {
+            return Err(RpcRequestError::Internal(anyhow::anyhow!(
+                "invalid number of outputs"
+            )));
+        }
         let _timer = client_id.start_timer_cpu(); // report when dropped
         match outputs
             .into_iter()
@@ -563,12 +891,14 @@ impl SolanaRpc {
             } else if let Some(output) = outputs.last() {
                 serde_json::to_vec(output)
             } else {
-                anyhow::bail!("output is not defined")
-            }
-            .map_err(Into::into),
-            Err(()) => {
-                anyhow::bail!("not all outputs created")
+                return Err(RpcRequestError::Internal(anyhow::anyhow!(
+                    "output is not defined"
+                )));
             }
+            .map_err(|error| RpcRequestError::Internal(error.into())),
+            Err(()) => Err(RpcRequestError::Internal(anyhow::anyhow!(
+                "not all outputs created"
+            ))),
         }
         .map(|mut body| {
             body.push(b'\n');
@@ -576,12 +906,296 @@ impl SolanaRpc {
         })
     }
 
+    /// Read-only GET API, query parameters mapped to JSON-RPC requests
+    pub async fn on_rest(
+        &self,
+        client_id: ClientId,
+        path: &str,
+        query: Option<&str>,
+    ) -> Result<(RpcRequestsStats, Vec<u8>, SolanaRpcStateVersion), RpcRequestError> {
+        let timer = client_id.start_timer_cpu();
+        let mut stats = RpcRequestsStats::default();
+
+        let params = rest_parse_query(query);
+        let get_param = |name: &str| params.get(name).map(|value| value.as_ref());
+        let get_commitment = || -> Result<CommitmentLevel, RpcRequestError> {
+            Ok(
+                rest_parse::<CommitmentConfig>(get_param("commitment"), "commitment")?
+                    .unwrap_or_default()
+                    .into(),
+            )
+        };
+
+        let request = match path {
+            "/v1/fees" => {
+                stats.recent_prioritization_fees += 1;
+                let filter = rest_parse_filter(&params)?;
+                RpcRequest::SolfeesSlots {
+                    jsonrpc: None,
+                    id: JsonrpcId::Null,
+                    filter,
+                    frontend: false,
+                }
+            }
+            "/v1/slot" => {
+                stats.slot += 1;
+                RpcRequest::Slot {
+                    jsonrpc: None,
+                    id: JsonrpcId::Null,
+                    commitment: get_commitment()?,
+                    min_context_slot: rest_parse(get_param("minContextSlot"), "minContextSlot")?,
+                }
+            }
+            "/v1/blockhash" => {
+                stats.latest_blockhash += 1;
+                RpcRequest::LatestBlockhash {
+                    jsonrpc: None,
+                    id: JsonrpcId::Null,
+                    mode: SolanaRpcMode::Solfees,
+                    commitment: get_commitment()?,
+                    rollback: LatestBlockhashRollback::new(
+                        rest_parse(get_param("rollback"), "rollback")?.unwrap_or(0),
+                        rest_parse(get_param("rollbackMs"), "rollbackMs")?,
+                        rest_parse(get_param("remainingValidity"), "remainingValidity")?,
+                    )
+                    .map_err(RpcRequestError::Rpc)?,
+                    min_context_slot: rest_parse(get_param("minContextSlot"), "minContextSlot")?,
+                }
+            }
+            path => match path.strip_prefix("/v1/leaders/") {
+                Some(epoch) => {
+                    stats.leader_schedule += 1;
+                    RpcRequest::LeaderSchedule {
+                        jsonrpc: None,
+                        id: JsonrpcId::Null,
+                        slot: None,
+                        epoch: rest_parse(Some(epoch), "epoch")?,
+                        commitment: CommitmentLevel::default(),
+                        identity: None,
+                    }
+                }
+                None => return Err(RpcRequestError::NotFound),
+            },
+        };
+        timer.stop_and_record();
+
+        let (mut outputs, version) = self.process_requests(&client_id, vec![request]).await?;
+        let output = outputs
+            .pop()
+            .ok_or_else(|| RpcRequestError::Internal(anyhow::anyhow!("output is not defined")))?;
+
+        let _timer = client_id.start_timer_cpu(); // report when dropped
+        match output {
+            JsonrpcOutputArced::Success(JsonrpcSuccessArced { result, .. }) => {
+                let mut body = serde_json::to_vec(&result)
+                    .map_err(|error| RpcRequestError::Internal(error.into()))?;
+                body.push(b'\n');
+                Ok((stats, body, version))
+            }
+            JsonrpcOutputArced::Failure(JsonrpcFailure { error, .. }) => {
+                Err(RpcRequestError::Rpc(error))
+            }
+        }
+    }
+
+    fn get_call_weight(&self, call: &JsonrpcCall) -> usize {
+        match call {
+            JsonrpcCall::MethodCall(call) => self
+                .request_calls_weights
+                .get(call.method.as_str())
+                .copied()
+                .unwrap_or(1),
+            JsonrpcCall::Notification(_) | JsonrpcCall::Invalid { .. } => 1,
+        }
+    }
+
+    // all requests are processed as one task against the same state snapshot
+    async fn process_requests(
+        &self,
+        client_id: &ClientId,
+        requests: Vec<RpcRequest>,
+    ) -> Result<(Vec<JsonrpcOutputArced>, SolanaRpcStateVersion), RpcRequestError> {
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let (tx, rx) = oneshot::channel();
+        match self.requests_tx.try_send(RpcRequestTask {
+            client_id: client_id.clone(),
+            requests,
+            shutdown: Arc::clone(&shutdown),
+            tx,
+        }) {
+            Ok(()) => {}
+            Err(async_channel::TrySendError::Full(_)) => return Err(RpcRequestError::QueueFull),
+            Err(async_channel::TrySendError::Closed(_)) => return Err(RpcRequestError::Shutdown),
+        }
+        metrics::requests_queue_size_inc();
+
+        tokio::select! {
+            () = sleep(self.request_timeout) => {
+                shutdown.store(true, Ordering::Relaxed);
+                Err(RpcRequestError::Timeout)
+            },
+            // sender is dropped only if processed loop is stopped
+            maybe_outputs = rx => maybe_outputs.map_err(|_error| RpcRequestError::Shutdown),
+        }
+    }
+
+    /// Solfees `getRecentPrioritizationFees` with typed result
+    pub async fn get_recent_prioritization_fees(
+        &self,
+        client_id: &ClientId,
+        config: ReqParamsSlotsSubscribeConfig,
+    ) -> Result<Vec<SlotsSubscribeOutput>, RpcRequestError> {
+        let filter = SlotSubscribeFilter::try_from(config).map_err(RpcRequestError::Rpc)?;
+        self.process_request_typed(
+            client_id,
+            RpcRequest::SolfeesSlots {
+                jsonrpc: None,
+                id: JsonrpcId::Null,
+                filter,
+                frontend: true,
+            },
+        )
+        .await
+    }
+
+    /// `getLatestBlockhash` with typed result
+    pub async fn get_latest_blockhash(
+        &self,
+        client_id: &ClientId,
+        commitment: CommitmentLevel,
+        rollback: LatestBlockhashRollback,
+        min_context_slot: Option<Slot>,
+    ) -> Result<RpcResponse<RpcBlockhash>, RpcRequestError> {
+        self.process_request_typed(
+            client_id,
+            RpcRequest::LatestBlockhash {
+                jsonrpc: None,
+                id: JsonrpcId::Null,
+                mode: SolanaRpcMode::Solfees,
+                commitment,
+                rollback,
+                min_context_slot,
+            },
+        )
+        .await
+    }
+
+    /// `getLeaderSchedule` with typed result, `None` if schedule for the epoch is not available
+    pub async fn get_leader_schedule(
+        &self,
+        client_id: &ClientId,
+        slot: Option<Slot>,
+        commitment: CommitmentLevel,
+        identity: Option<String>,
+    ) -> Result<Option<HashMap<String, Vec<usize>>>, RpcRequestError> {
+        if let Some(identity) = &identity {
+            let _ = verify_pubkey(identity).map_err(RpcRequestError::Rpc)?;
+        }
+
+        self.process_request_typed(
+            client_id,
+            RpcRequest::LeaderSchedule {
+                jsonrpc: None,
+                id: JsonrpcId::Null,
+                slot,
+                epoch: None,
+                commitment,
+                identity,
+            },
+        )
+        .await
+    }
+
+    // results are deserialized back from JSON, so the responses cache is shared with JSON-RPC
+    async fn process_request_typed<T: DeserializeOwned>(
+        &self,
+        client_id: &ClientId,
+        request: RpcRequest,
+    ) -> Result<T, RpcRequestError> {
+        let (mut outputs, _version) = self.process_requests(client_id, vec![request]).await?;
+        let output = outputs
+            .pop()
+            .ok_or_else(|| RpcRequestError::Internal(anyhow::anyhow!("output is not defined")))?;
+
+        let _timer = client_id.start_timer_cpu(); // report when dropped
+        match output {
+            JsonrpcOutputArced::Success(JsonrpcSuccessArced { result, .. }) => match result {
+                JsonrcpValueArced::Value(value) => serde_json::from_value(value),
+                result => serde_json::to_value(&result).and_then(serde_json::from_value),
+            }
+            .map_err(|error| RpcRequestError::Internal(error.into())),
+            JsonrpcOutputArced::Failure(JsonrpcFailure { error, .. }) => {
+                Err(RpcRequestError::Rpc(error))
+            }
+        }
+    }
+
+    pub fn slots_subscribe(
+        &self,
+        config: ReqParamsSlotsSubscribeConfig,
+    ) -> Result<SlotsSubscription, RpcRequestError> {
+        let filter = SlotSubscribeFilter::try_from(config).map_err(RpcRequestError::Rpc)?;
+        Ok(SlotsSubscription {
+            filter,
+            updates_rx: self.streams_tx.subscribe(),
+        })
+    }
+
+    /// Subscription is created before response, so invalid filter can be returned as HTTP error
+    pub fn sse_subscribe(
+        &self,
+        mode: SolanaRpcMode,
+        query: Option<&str>,
+        last_event_id: Option<Slot>,
+    ) -> Result<SseSubscription, RpcRequestError> {
+        let frontend = match mode {
+            SolanaRpcMode::Solfees => false,
+            SolanaRpcMode::SolfeesFrontend => true,
+            _ => return Err(RpcRequestError::NotFound),
+        };
+
+        let params = rest_parse_query(query);
+        let filter = rest_parse_filter(&params)?;
+        let last_event_id = match last_event_id {
+            Some(slot) => Some(slot),
+            None => rest_parse(
+                params.get("lastEventId").map(|value| value.as_ref()),
+                "lastEventId",
+            )?,
+        };
+
+        // subscribe before loading snapshot (updates are sent after snapshot is stored),
+        // live updates of replayed slots are skipped
+        let updates_rx = self.streams_tx.subscribe();
+        let replay = last_event_id
+            .map(|slot| {
+                self.state
+                    .load()
+                    .slots_info
+                    .range((Bound::Excluded(slot), Bound::Unbounded))
+                    .map(|(_slot, info)| info.get_filtered(&filter))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(SseSubscription {
+            frontend,
+            filter,
+            updates_rx,
+            replay,
+        })
+    }
+
     pub async fn on_websocket(
         self,
         client_id: ClientId,
         mode: SolanaRpcMode,
         websocket: HyperWebsocket,
+        compression: Option<WebSocketCompression>,
         mut shutdown_rx: broadcast::Receiver<()>,
+        limits: Arc<Limits>,
+        _permit: LimitsWebSocketPermit,
     ) {
         let ws_frontend = match mode {
             SolanaRpcMode::Solfees => false,
@@ -605,6 +1219,10 @@ impl SolanaRpc {
 
         let loop_close_reason = loop {
             if let Some(message) = websocket_tx_message.take() {
+                if limits.consume_egress(&client_id, message.len()).is_err() {
+                    metrics::limits_rejected_inc(&client_id, LimitKind::Egress);
+                    break Some(Some("egress limit exceeded"));
+                }
                 client_id.observe_egress_ws(message.len() as u64);
                 if websocket_tx.feed(message).await.is_err() {
                     break None;
@@ -667,11 +1285,12 @@ impl SolanaRpc {
                                 },
                                 Err(error) => Self::create_failure(call.jsonrpc, call.id, error),
                             };
-                            websocket_tx_message = Some(WebSocketMessage::Text(serde_json::to_string(&output).expect("failed to serialize")));
+                            let message = serde_json::to_string(&output).expect("failed to serialize");
+                            timer.stop_and_record();
+                            websocket_tx_message = Some(WebSocketCompression::encode(compression, message).await);
                         },
                         _ => break Some(Some("unknown subscription method")),
                     }
-                    timer.stop_and_record();
                 },
 
                 maybe_update = updates_rx.recv() => match maybe_update {
@@ -691,8 +1310,9 @@ impl SolanaRpc {
                         } else {
                             Self::create_success2(None, id.clone(), SlotsSubscribeOutputSolana::from(output))
                         };
-                        websocket_tx_message = Some(WebSocketMessage::Text(serde_json::to_string(&message).expect("failed to serialize")));
+                        let message = serde_json::to_string(&message).expect("failed to serialize");
                         timer.stop_and_record();
+                        websocket_tx_message = Some(WebSocketCompression::encode(compression, message).await);
                     }
                     Err(broadcast::error::RecvError::Closed) => break Some(None),
                     Err(broadcast::error::RecvError::Lagged(_)) => break Some(Some("subscription lagged")),
@@ -750,6 +1370,14 @@ impl SolanaRpc {
         JsonrpcOutputArced::Failure(JsonrpcFailure { jsonrpc, error, id })
     }
 
+    fn limit_exceeded_error(kind: LimitKind) -> JsonrpcError {
+        JsonrpcError {
+            code: JsonrpcErrorCode::ServerError(-32005),
+            message: format!("{} limit exceeded", kind.as_str()),
+            data: None,
+        }
+    }
+
     fn internal_error_with_data<R>(data: R) -> JsonrpcError
     where
         R: Serialize,
@@ -759,191 +1387,476 @@ impl SolanaRpc {
         error
     }
 
-    async fn get_next_requests(
-        rx: &Mutex<mpsc::Receiver<RpcRequestTask>>,
-    ) -> Option<RpcRequestTask> {
-        rx.lock().await.recv().await
-    }
-
-    async fn run_subscribe_update_loop(
-        mut redis_rx: broadcast::Receiver<RedisMessage>,
+    async fn run_update_loop(
+        mut bus_rx: mpsc::Receiver<BusMessage>,
+        state: Arc<ArcSwap<SolanaRpcState>>,
         streams_tx: broadcast::Sender<Arc<StreamsUpdateMessage>>,
+        position_tx: watch::Sender<Option<String>>,
     ) -> anyhow::Result<()> {
-        loop {
-            match redis_rx.recv().await {
-                Ok(RedisMessage::Geyser(message)) => match message {
-                    GeyserMessage::Status { slot, commitment } => {
-                        let _ = streams_tx
-                            .send(Arc::new(StreamsUpdateMessage::Status { slot, commitment }));
-                        metrics::set_slot(commitment, slot);
-                    }
-                    GeyserMessage::Slot {
-                        leader,
-                        slot,
-                        hash,
-                        time,
-                        height,
-                        parent_slot,
-                        parent_hash: _parent_hash,
-                        transactions,
-                    } => {
-                        let info = StreamsSlotInfo::new(
-                            leader,
-                            slot,
-                            parent_slot,
-                            hash,
-                            time,
-                            height,
-                            transactions,
-                        );
-                        let _ = streams_tx.send(Arc::new(StreamsUpdateMessage::Slot { info }));
-                    }
-                },
-                Ok(RedisMessage::Epoch { .. }) => continue,
-                Err(broadcast::error::RecvError::Closed) => return Ok(()),
-                Err(broadcast::error::RecvError::Lagged(_lag)) => {
-                    anyhow::bail!("run_subscribe_update_loop lagged")
+        let mut state_next = SolanaRpcState::default();
+        let mut messages = Vec::with_capacity(BUS_CHANNEL_SIZE);
+        let mut updates = Vec::new();
+        // apply everything what we already have before creating snapshot
+        while bus_rx.recv_many(&mut messages, BUS_CHANNEL_SIZE).await > 0 {
+            let mut position = None;
+            for message in messages.drain(..) {
+                match message {
+                    BusMessage::Position(value) => position = Some(value),
+                    message => state_next.apply(message, &mut updates),
                 }
             }
+            // snapshot is stored before updates are sent, so a new subscriber
+            // either receives an update or finds it in the snapshot
+            state.store(Arc::new(state_next.snapshot()));
+            for update in updates.drain(..) {
+                let _ = streams_tx.send(update);
+            }
+            if position.is_some() {
+                position_tx.send_replace(position);
+            }
         }
+        Ok(())
     }
 
-    async fn run_request_update_loop(
-        loop_index: usize,
-        mut redis_rx: broadcast::Receiver<RedisMessage>,
-        requests_rx: Arc<Mutex<mpsc::Receiver<RpcRequestTask>>>,
+    async fn run_request_loop(
+        requests_rx: async_channel::Receiver<RpcRequestTask>,
+        state: Arc<ArcSwap<SolanaRpcState>>,
     ) -> anyhow::Result<()> {
-        let mut latest_blockhash_storage = LatestBlockhashStorage::default();
-        let mut slots_info = BTreeMap::<Slot, StreamsSlotInfo>::new();
+        while let Ok(task) = requests_rx.recv().await {
+            metrics::requests_queue_size_dec();
+            if !task.shutdown.load(Ordering::Relaxed) {
+                let timer = task.client_id.start_timer_cpu();
+                let state = state.load();
+                let outputs = task
+                    .requests
+                    .into_iter()
+                    .map(|request| state.handle_request_task(request))
+                    .collect();
+                let _ = task.tx.send((outputs, state.get_version()));
+                timer.stop_and_record();
+            }
+        }
+        Ok(())
+    }
+}
 
-        let epoch_schedule = EpochSchedule::custom(432_000, 432_000, false);
-        let mut leader_schedule_map_solfees = HashMap::<Slot, Arc<JsonrcpValue>>::new();
-        let mut leader_schedule_map_rpc = HashMap::<Slot, Arc<JsonrcpValue>>::new();
+/// Server-Sent Events stream, slot number is used as event id
+#[derive(Debug)]
+pub struct SseSubscription {
+    frontend: bool,
+    filter: SlotSubscribeFilter,
+    updates_rx: broadcast::Receiver<Arc<StreamsUpdateMessage>>,
+    replay: Vec<SlotsSubscribeOutput>,
+}
 
-        loop {
-            tokio::select! {
-                biased;
+impl SseSubscription {
+    pub async fn run(
+        mut self,
+        client_id: ClientId,
+        mut events_tx: futures_mpsc::Sender<Bytes>,
+        mut shutdown_rx: broadcast::Receiver<()>,
+        limits: Arc<Limits>,
+        _permit: LimitsWebSocketPermit,
+    ) {
+        let mut heartbeat = interval_at(
+            Instant::now() + SSE_HEARTBEAT_INTERVAL,
+            SSE_HEARTBEAT_INTERVAL,
+        );
+        heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
-                maybe_message = redis_rx.recv() => match maybe_message {
-                    Ok(RedisMessage::Geyser(message)) => match message {
-                        GeyserMessage::Status { slot, commitment } => {
-                            latest_blockhash_storage.update_commitment(slot, commitment);
+        let mut replayed_slots = HashSet::new();
+        let mut events = std::mem::take(&mut self.replay)
+            .into_iter()
+            .map(|output| {
+                if let SlotsSubscribeOutput::Slot { slot, .. } = output {
+                    replayed_slots.insert(slot);
+                }
+                Self::encode(self.frontend, output)
+            })
+            .collect::<VecDeque<_>>();
 
-                            if let Some(info) = slots_info.get_mut(&slot) {
-                                info.commitment = commitment;
-                            }
+        loop {
+            let event = if let Some(event) = events.pop_front() {
+                event
+            } else {
+                tokio::select! {
+                    _ = shutdown_rx.recv() => break,
+                    _ = heartbeat.tick() => Bytes::from_static(b": heartbeat\n\n"),
+                    maybe_update = self.updates_rx.recv() => match maybe_update {
+                        Ok(update) => {
+                            let timer = client_id.start_timer_cpu();
+                            let output = match update.as_ref() {
+                                StreamsUpdateMessage::Status { slot, commitment } => {
+                                    SlotsSubscribeOutput::Status {
+                                        slot: *slot,
+                                        commitment: *commitment,
+                                    }
+                                }
+                                StreamsUpdateMessage::Slot { info } => {
+                                    if replayed_slots.remove(&info.summary.slot) {
+                                        continue;
+                                    }
+                                    info.get_filtered(&self.filter)
+                                }
+                            };
+                            let event = Self::encode(self.frontend, output);
+                            timer.stop_and_record();
+                            event
                         }
-                        GeyserMessage::Slot {
-                            leader,
-                            slot,
-                            hash,
-                            time,
-                            height,
-                            parent_slot,
-                            parent_hash: _parent_hash,
-                            transactions,
-                        } => {
-                            latest_blockhash_storage.push_block(slot, parent_slot, height, hash);
-
-                            let info = StreamsSlotInfo::new(leader, slot, parent_slot, hash, time, height, transactions);
-                            slots_info.insert(slot, info.clone());
-                            while slots_info.len() > MAX_NUM_RECENT_SLOT_INFO {
-                                slots_info.pop_first();
-                            }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                        Err(broadcast::error::RecvError::Lagged(_)) => {
+                            let event = "event: error\ndata: subscription lagged\n\n";
+                            let _ = events_tx.send(Bytes::from_static(event.as_bytes())).await;
+                            break;
                         }
+                    },
+                }
+            };
+
+            if limits.consume_egress(&client_id, event.len()).is_err() {
+                metrics::limits_rejected_inc(&client_id, LimitKind::Egress);
+                break;
+            }
+            client_id.observe_egress_ws(event.len() as u64);
+            // receiver is dropped when client is disconnected
+            if events_tx.send(event).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    fn encode(frontend: bool, output: SlotsSubscribeOutput) -> Bytes {
+        let (event, id) = match &output {
+            SlotsSubscribeOutput::Status { .. } => ("status", None),
+            SlotsSubscribeOutput::Slot { slot, .. } => ("slot", Some(*slot)),
+        };
+        let data = if frontend {
+            serde_json::to_string(&output)
+        } else {
+            serde_json::to_string(&SlotsSubscribeOutputSolana::from(output))
+        }
+        .expect("failed to serialize");
+
+        Bytes::from(match id {
+            Some(id) => format!("event: {event}\nid: {id}\ndata: {data}\n\n"),
+            None => format!("event: {event}\ndata: {data}\n\n"),
+        })
+    }
+}
+
+/// Filtered slots updates, same as `SlotsSubscribe` in WebSocket API
+#[derive(Debug)]
+pub struct SlotsSubscription {
+    filter: SlotSubscribeFilter,
+    updates_rx: broadcast::Receiver<Arc<StreamsUpdateMessage>>,
+}
+
+impl SlotsSubscription {
+    pub async fn recv(
+        &mut self,
+        client_id: &ClientId,
+    ) -> Result<SlotsSubscribeOutput, broadcast::error::RecvError> {
+        let update = self.updates_rx.recv().await?;
+        let _timer = client_id.start_timer_cpu(); // report when dropped
+        Ok(match update.as_ref() {
+            StreamsUpdateMessage::Status { slot, commitment } => SlotsSubscribeOutput::Status {
+                slot: *slot,
+                commitment: *commitment,
+            },
+            StreamsUpdateMessage::Slot { info } => info.get_filtered(&self.filter),
+        })
+    }
+}
+
+/// Slots of the state snapshot, changed with every new slot or commitment update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolanaRpcStateVersion {
+    pub processed: Slot,
+    pub confirmed: Slot,
+    pub finalized: Slot,
+}
+
+impl SolanaRpcStateVersion {
+    /// Weak because body depends on `Content-Encoding`
+    pub fn etag(self) -> String {
+        format!(
+            "W/\"{}-{}-{}\"",
+            self.processed, self.confirmed, self.finalized
+        )
+    }
+}
+
+/// Immutable snapshot of the data required for requests, replaced on every update from Redis
+#[derive(Debug, Default)]
+struct SolanaRpcState {
+    latest_blockhash_storage: Arc<LatestBlockhashStorage>,
+    slots_info: Arc<BTreeMap<Slot, StreamsSlotInfo>>,
+    leader_schedule_map_solfees: Arc<HashMap<Epoch, Arc<JsonrcpValue>>>,
+    leader_schedule_map_rpc: Arc<HashMap<Epoch, Arc<JsonrcpValue>>>,
+    responses_cache: RpcResponsesCache,
+}
+
+impl SolanaRpcState {
+    fn get_version(&self) -> SolanaRpcStateVersion {
+        SolanaRpcStateVersion {
+            processed: self.latest_blockhash_storage.slot_processed,
+            confirmed: self.latest_blockhash_storage.slot_confirmed,
+            finalized: self.latest_blockhash_storage.slot_finalized,
+        }
+    }
+
+    // parts are shared with the previous snapshot, `apply` copies only modified parts
+    // (once per snapshot, see `Arc::make_mut`)
+    fn snapshot(&self) -> Self {
+        Self {
+            latest_blockhash_storage: Arc::clone(&self.latest_blockhash_storage),
+            slots_info: Arc::clone(&self.slots_info),
+            leader_schedule_map_solfees: Arc::clone(&self.leader_schedule_map_solfees),
+            leader_schedule_map_rpc: Arc::clone(&self.leader_schedule_map_rpc),
+            responses_cache: RpcResponsesCache::default(),
+        }
+    }
+
+    fn apply(&mut self, message: BusMessage, updates: &mut Vec<Arc<StreamsUpdateMessage>>) {
+        match message {
+            BusMessage::Geyser(GeyserMessage::Status { slot, commitment }) => {
+                Arc::make_mut(&mut self.latest_blockhash_storage)
+                    .update_commitment(slot, commitment);
+                if let Some(info) = Arc::make_mut(&mut self.slots_info).get_mut(&slot) {
+                    info.commitment = commitment;
+                }
+
+                updates.push(Arc::new(StreamsUpdateMessage::Status { slot, commitment }));
+                metrics::set_slot(commitment, slot);
+            }
+            BusMessage::Geyser(message @ GeyserMessage::Slot { .. }) => {
+                if let GeyserMessage::SlotSummary(summary) = message.into_summary() {
+                    self.push_slot_summary(summary, updates);
+                }
+            }
+            BusMessage::Geyser(GeyserMessage::SlotSummary(summary)) => {
+                self.push_slot_summary(summary, updates);
+            }
+            BusMessage::Epoch {
+                epoch,
+                leader_schedule_solfees,
+                leader_schedule_rpc,
+            } => {
+                info!(epoch, "epoch received");
+                Arc::make_mut(&mut self.leader_schedule_map_solfees)
+                    .insert(epoch, leader_schedule_solfees);
+                Arc::make_mut(&mut self.leader_schedule_map_rpc).insert(epoch, leader_schedule_rpc);
+            }
+            // handled in `run_update_loop`
+            BusMessage::Position(_) => {}
+        }
+    }
+
+    fn push_slot_summary(
+        &mut self,
+        summary: Arc<SlotSummary>,
+        updates: &mut Vec<Arc<StreamsUpdateMessage>>,
+    ) {
+        Arc::make_mut(&mut self.latest_blockhash_storage).push_block(
+            summary.slot,
+            summary.parent_slot,
+            summary.height,
+            summary.hash,
+            summary.time,
+        );
+
+        let info = StreamsSlotInfo::new(summary);
+        let slots_info = Arc::make_mut(&mut self.slots_info);
+        slots_info.insert(info.summary.slot, info.clone());
+        while slots_info.len() > MAX_NUM_RECENT_SLOT_INFO {
+            slots_info.pop_first();
+        }
+
+        updates.push(Arc::new(StreamsUpdateMessage::Slot { info }));
+    }
+
+    fn handle_request_task(&self, request: RpcRequest) -> JsonrpcOutputArced {
+        let Some(cache_key) = request.get_cache_key() else {
+            return self.handle_request(request);
+        };
+
+        if let Some(value) = self.responses_cache.get(&cache_key) {
+            let (jsonrpc, id) = request.into_jsonrpc_id();
+            return SolanaRpc::create_success(jsonrpc, id, value);
+        }
+
+        match self.handle_request(request) {
+            JsonrpcOutputArced::Success(JsonrpcSuccessArced {
+                jsonrpc,
+                result,
+                id,
+            }) => match to_raw_value(&result) {
+                Ok(value) => {
+                    let value = JsonrpcValueCached(value.into());
+                    self.responses_cache.insert(cache_key, value.clone());
+                    SolanaRpc::create_success(jsonrpc, id, value)
+                }
+                Err(_error) => SolanaRpc::create_success(jsonrpc, id, result),
+            },
+            JsonrpcOutputArced::Failure(failure) => JsonrpcOutputArced::Failure(failure),
+        }
+    }
+
+    fn handle_request(&self, request: RpcRequest) -> JsonrpcOutputArced {
+        let latest_blockhash_storage = &self.latest_blockhash_storage;
+        let slots_info = &self.slots_info;
+        let leader_schedule_map_solfees = &self.leader_schedule_map_solfees;
+        let leader_schedule_map_rpc = &self.leader_schedule_map_rpc;
+
+        match request {
+            RpcRequest::BlockHeight {
+                jsonrpc,
+                id,
+                commitment,
+                min_context_slot,
+            } => {
+                let slot = latest_blockhash_storage.get_slot(commitment);
+                if let Some(min_context_slot) = min_context_slot {
+                    if slot < min_context_slot {
+                        let error =
+                            RpcCustomError::MinContextSlotNotReached { context_slot: slot }.into();
+                        return SolanaRpc::create_failure(jsonrpc, id, error);
                     }
-                    Ok(RedisMessage::Epoch { epoch, leader_schedule_solfees, leader_schedule_rpc }) => {
-                        info!(loop_index, epoch, "epoch received");
-                        leader_schedule_map_solfees.insert(epoch, leader_schedule_solfees);
-                        leader_schedule_map_rpc.insert(epoch, leader_schedule_rpc);
-                        continue;
+                }
+
+                match latest_blockhash_storage.slots.get(&slot) {
+                    Some(value) => SolanaRpc::create_success2(jsonrpc, id, value.height),
+                    None => SolanaRpc::create_failure(
+                        jsonrpc,
+                        id,
+                        SolanaRpc::internal_error_with_data("no slot"),
+                    ),
+                }
+            }
+            RpcRequest::BlockTime { jsonrpc, id, slot } => {
+                if let Some(error) = latest_blockhash_storage.check_cleaned_up(slot) {
+                    return SolanaRpc::create_failure(jsonrpc, id, error);
+                }
+
+                match latest_blockhash_storage.slots.get(&slot) {
+                    Some(value) if value.commitment != CommitmentLevel::Processed => {
+                        SolanaRpc::create_success2(jsonrpc, id, value.time)
+                    }
+                    _ => SolanaRpc::create_failure(
+                        jsonrpc,
+                        id,
+                        RpcCustomError::BlockNotAvailable { slot }.into(),
+                    ),
+                }
+            }
+            RpcRequest::Blocks {
+                jsonrpc,
+                id,
+                start_slot,
+                end_slot,
+                commitment,
+            } => {
+                let end_slot = end_slot
+                    .unwrap_or(Slot::MAX)
+                    .min(latest_blockhash_storage.get_slot(commitment));
+                if end_slot < start_slot {
+                    return SolanaRpc::create_success2(jsonrpc, id, Vec::<Slot>::new());
+                }
+                if end_slot - start_slot > MAX_GET_CONFIRMED_BLOCKS_RANGE {
+                    let error = JsonrpcError::invalid_params(format!(
+                        "Slot range too large; max {MAX_GET_CONFIRMED_BLOCKS_RANGE}"
+                    ));
+                    return SolanaRpc::create_failure(jsonrpc, id, error);
+                }
+                if let Some(error) = latest_blockhash_storage.check_cleaned_up(start_slot) {
+                    return SolanaRpc::create_failure(jsonrpc, id, error);
+                }
+
+                let slots = latest_blockhash_storage
+                    .slots
+                    .range(start_slot..=end_slot)
+                    .filter(|(_slot, value)| value.commitment >= commitment)
+                    .map(|(slot, _value)| *slot)
+                    .collect::<Vec<_>>();
+                SolanaRpc::create_success2(jsonrpc, id, slots)
+            }
+            RpcRequest::FeeForMessage {
+                jsonrpc,
+                id,
+                blockhash,
+                fee,
+                commitment,
+                min_context_slot,
+            } => {
+                let slot = latest_blockhash_storage.get_slot(commitment);
+                if let Some(min_context_slot) = min_context_slot {
+                    if slot < min_context_slot {
+                        let error =
+                            RpcCustomError::MinContextSlotNotReached { context_slot: slot }.into();
+                        return SolanaRpc::create_failure(jsonrpc, id, error);
                     }
-                    Err(broadcast::error::RecvError::Closed) => break,
-                    Err(broadcast::error::RecvError::Lagged(_lag)) => anyhow::bail!("run_request_update_loop#{loop_index} lagged"),
-                },
+                }
 
-                maybe_rpc_requests_task = Self::get_next_requests(&requests_rx) => match maybe_rpc_requests_task {
-                    Some(task) => {
-                        metrics::requests_queue_size_dec();
-                        if !task.shutdown.load(Ordering::Relaxed) {
-                            let timer = task.client_id.start_timer_cpu();
-                            let _ = task.tx.send(Self::handle_request_task(
-                                task.request,
-                                &latest_blockhash_storage,
-                                &slots_info,
-                                &epoch_schedule,
-                                &leader_schedule_map_solfees,
-                                &leader_schedule_map_rpc
-                            ));
-                            timer.stop_and_record();
-                        }
+                SolanaRpc::create_success2(
+                    jsonrpc,
+                    id,
+                    RpcResponse {
+                        context: RpcResponseContext::new(slot),
+                        value: latest_blockhash_storage
+                            .is_blockhash_valid(slot, &blockhash)
+                            .then_some(fee),
                     },
-                    None => break,
-                },
+                )
             }
-        }
-
-        Ok(())
-    }
+            RpcRequest::IsBlockhashValid {
+                jsonrpc,
+                id,
+                blockhash,
+                commitment,
+                min_context_slot,
+            } => {
+                let slot = latest_blockhash_storage.get_slot(commitment);
+                if let Some(min_context_slot) = min_context_slot {
+                    if slot < min_context_slot {
+                        let error =
+                            RpcCustomError::MinContextSlotNotReached { context_slot: slot }.into();
+                        return SolanaRpc::create_failure(jsonrpc, id, error);
+                    }
+                }
 
-    fn handle_request_task(
-        request: RpcRequest,
-        latest_blockhash_storage: &LatestBlockhashStorage,
-        slots_info: &BTreeMap<Slot, StreamsSlotInfo>,
-        epoch_schedule: &EpochSchedule,
-        leader_schedule_map_solfees: &HashMap<Slot, Arc<JsonrcpValue>>,
-        leader_schedule_map_rpc: &HashMap<Slot, Arc<JsonrcpValue>>,
-    ) -> JsonrpcOutputArced {
-        match request {
+                SolanaRpc::create_success2(
+                    jsonrpc,
+                    id,
+                    RpcResponse {
+                        context: RpcResponseContext::new(slot),
+                        value: latest_blockhash_storage.is_blockhash_valid(slot, &blockhash),
+                    },
+                )
+            }
             RpcRequest::LatestBlockhash {
                 jsonrpc,
                 id,
                 commitment,
                 rollback,
                 min_context_slot,
+                ..
             } => {
-                if rollback > MAX_PROCESSING_AGE {
-                    let error = JsonrpcError::invalid_params(format!(
-                        "rollback exceeds {MAX_PROCESSING_AGE}"
-                    ));
-                    return Self::create_failure(jsonrpc, id, error);
-                }
-
-                let mut slot = match commitment {
-                    CommitmentLevel::Processed => latest_blockhash_storage.slot_processed,
-                    CommitmentLevel::Confirmed => latest_blockhash_storage.slot_confirmed,
-                    CommitmentLevel::Finalized => latest_blockhash_storage.slot_finalized,
-                };
+                let slot = latest_blockhash_storage.get_slot(commitment);
 
                 if let Some(min_context_slot) = min_context_slot {
                     if slot < min_context_slot {
                         let error =
                             RpcCustomError::MinContextSlotNotReached { context_slot: slot }.into();
-                        return Self::create_failure(jsonrpc, id, error);
+                        return SolanaRpc::create_failure(jsonrpc, id, error);
                     }
                 }
 
-                let Some(mut value) = latest_blockhash_storage.slots.get(&slot) else {
-                    return Self::create_failure(
-                        jsonrpc,
-                        id,
-                        SolanaRpc::internal_error_with_data("no slot"),
-                    );
+                let (slot, value) = match latest_blockhash_storage.rollback(slot, rollback) {
+                    Ok(value) => value,
+                    Err(error) => return SolanaRpc::create_failure(jsonrpc, id, error),
                 };
-                for _ in 0..rollback {
-                    let Some(parent_value) = latest_blockhash_storage.slots.get(&value.parent)
-                    else {
-                        return Self::create_failure(
-                            jsonrpc,
-                            id,
-                            JsonrpcError::invalid_params("not enought slots in the storage"),
-                        );
-                    };
-                    slot = value.parent;
-                    value = parent_value;
-                }
 
-                Self::create_success2(
+                SolanaRpc::create_success2(
                     jsonrpc,
                     id,
                     RpcResponse {
@@ -964,7 +1877,7 @@ impl SolanaRpc {
                 identity,
             } => {
                 if let Some(epoch) = epoch {
-                    Self::create_success(jsonrpc, id, leader_schedule_map_solfees.get(&epoch))
+                    SolanaRpc::create_success(jsonrpc, id, leader_schedule_map_solfees.get(&epoch))
                 } else {
                     let slot = slot.unwrap_or({
                         match commitment {
@@ -973,7 +1886,7 @@ impl SolanaRpc {
                             CommitmentLevel::Finalized => latest_blockhash_storage.slot_finalized,
                         }
                     });
-                    let epoch = epoch_schedule.get_epoch(slot);
+                    let epoch = EPOCH_SCHEDULE.get_epoch(slot);
 
                     if let Some(identity) = identity {
                         let mut map = HashMap::new();
@@ -983,9 +1896,9 @@ impl SolanaRpc {
                         {
                             map.insert(identity, slots);
                         }
-                        Self::create_success2(jsonrpc, id, Some(&map))
+                        SolanaRpc::create_success2(jsonrpc, id, Some(&map))
                     } else {
-                        Self::create_success(jsonrpc, id, leader_schedule_map_rpc.get(&epoch))
+                        SolanaRpc::create_success(jsonrpc, id, leader_schedule_map_rpc.get(&epoch))
                     }
                 }
             }
@@ -994,6 +1907,7 @@ impl SolanaRpc {
                 id,
                 pubkeys,
                 percentile,
+                ..
             } => {
                 let result = slots_info
                     .iter()
@@ -1003,7 +1917,7 @@ impl SolanaRpc {
                     })
                     .collect::<Vec<_>>();
 
-                Self::create_success(jsonrpc, id, result)
+                SolanaRpc::create_success(jsonrpc, id, result)
             }
             RpcRequest::Slot {
                 jsonrpc,
@@ -1021,11 +1935,11 @@ impl SolanaRpc {
                     if slot < min_context_slot {
                         let error =
                             RpcCustomError::MinContextSlotNotReached { context_slot: slot }.into();
-                        return Self::create_failure(jsonrpc, id, error);
+                        return SolanaRpc::create_failure(jsonrpc, id, error);
                     }
                 }
 
-                Self::create_success2(jsonrpc, id, slot)
+                SolanaRpc::create_success2(jsonrpc, id, slot)
             }
             RpcRequest::SolfeesSlots {
                 jsonrpc,
@@ -1035,14 +1949,14 @@ impl SolanaRpc {
             } => {
                 let outputs = slots_info.values().map(|info| info.get_filtered(&filter));
                 if frontend {
-                    Self::create_success(jsonrpc, id, outputs.collect::<Vec<_>>())
+                    SolanaRpc::create_success(jsonrpc, id, outputs.collect::<Vec<_>>())
                 } else {
                     match outputs
                         .map(SolfeesPrioritizationFee::try_from)
                         .collect::<Result<Vec<_>, JsonrpcError>>()
                     {
-                        Ok(outputs) => Self::create_success(jsonrpc, id, outputs),
-                        Err(error) => Self::create_failure(jsonrpc, id, error),
+                        Ok(outputs) => SolanaRpc::create_success(jsonrpc, id, outputs),
+                        Err(error) => SolanaRpc::create_failure(jsonrpc, id, error),
                     }
                 }
             }
@@ -1057,6 +1971,56 @@ pub struct RpcLatestBlockhashConfigTriton {
     pub context: RpcContextConfig,
     #[serde(default)]
     pub rollback: usize,
+    #[serde(default)]
+    pub rollback_ms: Option<u64>,
+    #[serde(default)]
+    pub remaining_validity: Option<u64>,
+}
+
+/// How far `getLatestBlockhash` should go back from the tip
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LatestBlockhashRollback {
+    /// Number of blocks
+    Slots(usize),
+    /// Block with time closest to `now - ms`
+    Ms(u64),
+    /// Oldest block which is still valid for at least this number of blocks
+    RemainingValidity(u64),
+}
+
+impl LatestBlockhashRollback {
+    pub fn new(
+        rollback: usize,
+        rollback_ms: Option<u64>,
+        remaining_validity: Option<u64>,
+    ) -> Result<Self, JsonrpcError> {
+        let value = match (rollback, rollback_ms, remaining_validity) {
+            (rollback, None, None) => Self::Slots(rollback),
+            (0, Some(ms), None) => Self::Ms(ms),
+            (0, None, Some(blocks)) => Self::RemainingValidity(blocks),
+            _ => {
+                return Err(JsonrpcError::invalid_params(
+                    "only one of `rollback`, `rollbackMs` and `remainingValidity` is allowed",
+                ))
+            }
+        };
+
+        let max_ms = MAX_PROCESSING_AGE as u64 * DEFAULT_MS_PER_SLOT;
+        match value {
+            Self::Slots(rollback) if rollback > MAX_PROCESSING_AGE => Err(
+                JsonrpcError::invalid_params(format!("rollback exceeds {MAX_PROCESSING_AGE}")),
+            ),
+            Self::Ms(ms) if ms > max_ms => Err(JsonrpcError::invalid_params(format!(
+                "rollbackMs exceeds {max_ms}"
+            ))),
+            Self::RemainingValidity(blocks) if blocks > MAX_PROCESSING_AGE as u64 => {
+                Err(JsonrpcError::invalid_params(format!(
+                    "remainingValidity exceeds {MAX_PROCESSING_AGE}"
+                )))
+            }
+            value => Ok(value),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -1085,8 +2049,106 @@ fn verify_pubkey(input: &str) -> Result<Pubkey, JsonrpcError> {
         .map_err(|e| JsonrpcError::invalid_params(format!("Invalid param: {e:?}")))
 }
 
+fn verify_hash(input: &str) -> Result<Hash, JsonrpcError> {
+    input
+        .parse()
+        .map_err(|e| JsonrpcError::invalid_params(format!("Invalid param: {e:?}")))
+}
+
+// same limits and errors as in `decode_and_deserialize` of the original RPC
+fn decode_message_fee(data: &str) -> Result<(Hash, u64), JsonrpcError> {
+    const MAX_BASE64_SIZE: usize = 1644; // Golden, bump if PACKET_DATA_SIZE changes
+    const TYPE_NAME: &str = "solana_sdk::message::VersionedMessage";
+
+    if data.len() > MAX_BASE64_SIZE {
+        return Err(JsonrpcError::invalid_params(format!(
+            "base64 encoded {TYPE_NAME} too large: {} bytes (max: encoded/raw {MAX_BASE64_SIZE}/{PACKET_DATA_SIZE})",
+            data.len(),
+        )));
+    }
+    let data = BASE64_STANDARD.decode(data).map_err(|error| {
+        JsonrpcError::invalid_params(format!("invalid base64 encoding: {error}"))
+    })?;
+    if data.len() > PACKET_DATA_SIZE {
+        return Err(JsonrpcError::invalid_params(format!(
+            "decoded {TYPE_NAME} too large: {} bytes (max: {PACKET_DATA_SIZE} bytes)",
+            data.len(),
+        )));
+    }
+    let message = bincode::options()
+        .with_limit(PACKET_DATA_SIZE as u64)
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .deserialize_from::<_, VersionedMessage>(&data[..])
+        .map_err(|error| {
+            JsonrpcError::invalid_params(format!("failed to deserialize {TYPE_NAME}: {error}"))
+        })?;
+
+    let invalid_message = |error: String| {
+        JsonrpcError::invalid_params(format!("invalid transaction message: {error}"))
+    };
+    message
+        .sanitize()
+        .map_err(|error| invalid_message(error.to_string()))?;
+    // programs can not be loaded from lookup tables, so static keys are enough
+    let account_keys = AccountKeys::new(message.static_account_keys(), None);
+    let (sigs_count, budget_limits) = GeyserTransaction::process_message(&message, &account_keys)
+        .map_err(|error| invalid_message(error.to_string()))?;
+
+    let fee = LAMPORTS_PER_SIGNATURE * sigs_count as u64
+        + (budget_limits.compute_unit_price as u128 * budget_limits.compute_unit_limit as u128)
+            .div_ceil(MICRO_LAMPORTS_PER_LAMPORT) as u64;
+    Ok((*message.recent_blockhash(), fee))
+}
+
+fn rest_parse_query(query: Option<&str>) -> HashMap<Cow<'_, str>, Cow<'_, str>> {
+    form_urlencoded::parse(query.unwrap_or_default().as_bytes()).collect()
+}
+
+fn rest_parse_filter(
+    params: &HashMap<Cow<'_, str>, Cow<'_, str>>,
+) -> Result<SlotSubscribeFilter, RpcRequestError> {
+    let get_param = |name: &str| params.get(name).map(|value| value.as_ref());
+    ReqParamsSlotsSubscribeConfig {
+        read_write: rest_parse_list(get_param("readWrite"), "readWrite")?,
+        read_only: rest_parse_list(get_param("readOnly"), "readOnly")?,
+        levels: rest_parse_list(get_param("levels"), "levels")?,
+        skip_zeros: rest_parse(get_param("skipZeros"), "skipZeros")?.unwrap_or(false),
+    }
+    .try_into()
+    .map_err(RpcRequestError::Rpc)
+}
+
+fn rest_parse_value<T: FromStr>(value: &str, name: &str) -> Result<T, RpcRequestError> {
+    value.parse().map_err(|_error| {
+        RpcRequestError::Rpc(JsonrpcError::invalid_params(format!(
+            "invalid {name}: {value}"
+        )))
+    })
+}
+
+fn rest_parse<T: FromStr>(value: Option<&str>, name: &str) -> Result<Option<T>, RpcRequestError> {
+    value.map(|value| rest_parse_value(value, name)).transpose()
+}
+
+// comma separated values
+fn rest_parse_list<T: FromStr>(value: Option<&str>, name: &str) -> Result<Vec<T>, RpcRequestError> {
+    value
+        .into_iter()
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| rest_parse_value(value, name))
+        .collect()
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct RpcRequestsStats {
+    pub block_height: u64,
+    pub block_time: u64,
+    pub blocks: u64,
+    pub fee_for_message: u64,
+    pub is_blockhash_valid: u64,
     pub latest_blockhash: u64,
     pub leader_schedule: u64,
     pub recent_prioritization_fees: u64,
@@ -1097,18 +2159,52 @@ pub struct RpcRequestsStats {
 #[derive(Debug)]
 struct RpcRequestTask {
     client_id: ClientId,
-    request: RpcRequest,
+    requests: Vec<RpcRequest>,
     shutdown: Arc<AtomicBool>,
-    tx: oneshot::Sender<JsonrpcOutputArced>,
+    tx: oneshot::Sender<(Vec<JsonrpcOutputArced>, SolanaRpcStateVersion)>,
 }
 
 #[derive(Debug)]
 enum RpcRequest {
+    BlockHeight {
+        jsonrpc: Option<JsonrpcVersion>,
+        id: JsonrpcId,
+        commitment: CommitmentLevel,
+        min_context_slot: Option<Slot>,
+    },
+    BlockTime {
+        jsonrpc: Option<JsonrpcVersion>,
+        id: JsonrpcId,
+        slot: Slot,
+    },
+    Blocks {
+        jsonrpc: Option<JsonrpcVersion>,
+        id: JsonrpcId,
+        start_slot: Slot,
+        end_slot: Option<Slot>,
+        commitment: CommitmentLevel,
+    },
+    FeeForMessage {
+        jsonrpc: Option<JsonrpcVersion>,
+        id: JsonrpcId,
+        blockhash: Hash,
+        fee: u64,
+        commitment: CommitmentLevel,
+        min_context_slot: Option<Slot>,
+    },
+    IsBlockhashValid {
+        jsonrpc: Option<JsonrpcVersion>,
+        id: JsonrpcId,
+        blockhash: Hash,
+        commitment: CommitmentLevel,
+        min_context_slot: Option<Slot>,
+    },
     LatestBlockhash {
         jsonrpc: Option<JsonrpcVersion>,
         id: JsonrpcId,
+        mode: SolanaRpcMode,
         commitment: CommitmentLevel,
-        rollback: usize,
+        rollback: LatestBlockhashRollback,
         min_context_slot: Option<Slot>,
     },
     LeaderSchedule {
@@ -1122,6 +2218,7 @@ enum RpcRequest {
     RecentPrioritizationFees {
         jsonrpc: Option<JsonrpcVersion>,
         id: JsonrpcId,
+        mode: SolanaRpcMode,
         pubkeys: Vec<Pubkey>,
         percentile: Option<u16>,
     },
@@ -1139,7 +2236,126 @@ enum RpcRequest {
     },
 }
 
+impl RpcRequest {
+    fn into_jsonrpc_id(self) -> (Option<JsonrpcVersion>, JsonrpcId) {
+        match self {
+            Self::BlockHeight { jsonrpc, id, .. } => (jsonrpc, id),
+            Self::BlockTime { jsonrpc, id, .. } => (jsonrpc, id),
+            Self::Blocks { jsonrpc, id, .. } => (jsonrpc, id),
+            Self::FeeForMessage { jsonrpc, id, .. } => (jsonrpc, id),
+            Self::IsBlockhashValid { jsonrpc, id, .. } => (jsonrpc, id),
+            Self::LatestBlockhash { jsonrpc, id, .. } => (jsonrpc, id),
+            Self::LeaderSchedule { jsonrpc, id, .. } => (jsonrpc, id),
+            Self::RecentPrioritizationFees { jsonrpc, id, .. } => (jsonrpc, id),
+            Self::Slot { jsonrpc, id, .. } => (jsonrpc, id),
+            Self::SolfeesSlots { jsonrpc, id, .. } => (jsonrpc, id),
+        }
+    }
+
+    // requests with `min_context_slot` are not cached because result depends from it,
+    // `rollbackMs` depends from the current time; mode is part of the key because
+    // the same method accepts different params and can produce different output per mode,
+    // for `SolfeesSlots` mode is already encoded in `frontend`
+    fn get_cache_key(&self) -> Option<RpcRequestCacheKey> {
+        match self {
+            Self::LatestBlockhash {
+                mode,
+                commitment,
+                rollback,
+                min_context_slot: None,
+                ..
+            } if !matches!(rollback, LatestBlockhashRollback::Ms(_)) => {
+                Some(RpcRequestCacheKey::LatestBlockhash {
+                    mode: *mode,
+                    commitment: *commitment,
+                    rollback: *rollback,
+                })
+            }
+            Self::RecentPrioritizationFees {
+                mode,
+                pubkeys,
+                percentile,
+                ..
+            } => Some(RpcRequestCacheKey::RecentPrioritizationFees {
+                mode: *mode,
+                pubkeys: normalize_pubkeys(pubkeys),
+                percentile: *percentile,
+            }),
+            Self::SolfeesSlots {
+                filter, frontend, ..
+            } => Some(RpcRequestCacheKey::SolfeesSlots {
+                read_write: normalize_pubkeys(&filter.read_write),
+                read_only: normalize_pubkeys(&filter.read_only),
+                levels: filter.levels.clone(),
+                skip_zeros: filter.skip_zeros,
+                frontend: *frontend,
+            }),
+            _ => None,
+        }
+    }
+}
+
+// fees are calculated as max over accounts, so order and duplicates do not matter
+fn normalize_pubkeys(pubkeys: &[Pubkey]) -> Vec<Pubkey> {
+    let mut pubkeys = pubkeys.to_vec();
+    pubkeys.sort_unstable();
+    pubkeys.dedup();
+    pubkeys
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum RpcRequestCacheKey {
+    LatestBlockhash {
+        mode: SolanaRpcMode,
+        commitment: CommitmentLevel,
+        rollback: LatestBlockhashRollback,
+    },
+    RecentPrioritizationFees {
+        mode: SolanaRpcMode,
+        pubkeys: Vec<Pubkey>,
+        percentile: Option<u16>,
+    },
+    SolfeesSlots {
+        read_write: Vec<Pubkey>,
+        read_only: Vec<Pubkey>,
+        levels: Vec<u16>,
+        skip_zeros: bool,
+        frontend: bool,
+    },
+}
+
+impl RpcRequestCacheKey {
+    const fn as_str(&self) -> &'static str {
+        match self {
+            Self::LatestBlockhash { .. } => "get_latest_blockhash",
+            Self::RecentPrioritizationFees { .. } => "get_recent_prioritization_fees",
+            Self::SolfeesSlots { .. } => "get_recent_prioritization_fees_solfees",
+        }
+    }
+}
+
+/// Serialized results, created for each `SolanaRpcState` snapshot, sharded map so workers
+/// do not wait on a single lock; a value can be computed twice on concurrent misses
 #[derive(Debug, Default)]
+struct RpcResponsesCache {
+    values: DashMap<RpcRequestCacheKey, JsonrpcValueCached>,
+}
+
+impl RpcResponsesCache {
+    fn get(&self, key: &RpcRequestCacheKey) -> Option<JsonrpcValueCached> {
+        let value = self.values.get(key).map(|value| value.value().clone());
+        metrics::requests_cache_observe(key.as_str(), value.is_some());
+        value
+    }
+
+    fn insert(&self, key: RpcRequestCacheKey, value: JsonrpcValueCached) {
+        if self.values.len() < MAX_NUM_CACHED_RESPONSES {
+            self.values.insert(key, value);
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
 struct LatestBlockhashStorage {
     slot_processed: Slot,
     slot_confirmed: Slot,
@@ -1147,9 +2363,113 @@ struct LatestBlockhashStorage {
     confirmed: BTreeSet<Slot>,
     finalized: BTreeSet<Slot>,
     slots: BTreeMap<Slot, LatestBlockhashSlot>,
+    hashes: HashMap<Hash, Slot>,
 }
 
 impl LatestBlockhashStorage {
+    const fn get_slot(&self, commitment: CommitmentLevel) -> Slot {
+        match commitment {
+            CommitmentLevel::Processed => self.slot_processed,
+            CommitmentLevel::Confirmed => self.slot_confirmed,
+            CommitmentLevel::Finalized => self.slot_finalized,
+        }
+    }
+
+    // only retained slots can be served, older slots are reported as cleaned up
+    fn check_cleaned_up(&self, slot: Slot) -> Option<JsonrpcError> {
+        let first_available_block = *self.slots.keys().next()?;
+        (slot < first_available_block).then(|| {
+            RpcCustomError::BlockCleanedUp {
+                slot,
+                first_available_block,
+            }
+            .into()
+        })
+    }
+
+    // walk parents from the `tip`, block time has only seconds precision
+    fn rollback(
+        &self,
+        tip: Slot,
+        rollback: LatestBlockhashRollback,
+    ) -> Result<(Slot, &LatestBlockhashSlot), JsonrpcError> {
+        let mut slot = tip;
+        let mut value = self
+            .slots
+            .get(&slot)
+            .ok_or_else(|| SolanaRpc::internal_error_with_data("no slot"))?;
+
+        match rollback {
+            LatestBlockhashRollback::Slots(rollback) => {
+                for _ in 0..rollback {
+                    let parent_value = self.slots.get(&value.parent).ok_or_else(|| {
+                        JsonrpcError::invalid_params("not enought slots in the storage")
+                    })?;
+                    slot = value.parent;
+                    value = parent_value;
+                }
+            }
+            LatestBlockhashRollback::Ms(ms) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as i64;
+                let target = now - ms as i64;
+                let distance = |value: &LatestBlockhashSlot| (value.time * 1_000 - target).abs();
+
+                let mut best = (slot, value);
+                for _ in 0..MAX_PROCESSING_AGE {
+                    if value.time * 1_000 <= target {
+                        break;
+                    }
+                    let Some(parent_value) = self.slots.get(&value.parent) else {
+                        break;
+                    };
+                    slot = value.parent;
+                    value = parent_value;
+                    if distance(value) < distance(best.1) {
+                        best = (slot, value);
+                    }
+                }
+                (slot, value) = best;
+            }
+            LatestBlockhashRollback::RemainingValidity(blocks) => {
+                let min_height = (value.height + blocks).saturating_sub(MAX_PROCESSING_AGE as u64);
+                while let Some(parent_value) = self.slots.get(&value.parent) {
+                    if parent_value.height < min_height {
+                        break;
+                    }
+                    slot = value.parent;
+                    value = parent_value;
+                }
+            }
+        }
+
+        Ok((slot, value))
+    }
+
+    // blockhash should be in the chain of the tip and not older than `MAX_PROCESSING_AGE` blocks
+    fn is_blockhash_valid(&self, tip: Slot, blockhash: &Hash) -> bool {
+        let Some(slot) = self.hashes.get(blockhash).copied() else {
+            return false;
+        };
+        let (Some(tip_value), Some(value)) = (self.slots.get(&tip), self.slots.get(&slot)) else {
+            return false;
+        };
+        if tip_value.height > value.height + MAX_PROCESSING_AGE as u64 {
+            return false;
+        }
+
+        let mut current = tip;
+        while current > slot {
+            match self.slots.get(&current) {
+                Some(value) => current = value.parent,
+                None => return false,
+            }
+        }
+        current == slot
+    }
+
     fn update_slots(&mut self) {
         for (slot, entry) in self.slots.iter().rev() {
             match entry.commitment {
@@ -1171,7 +2491,14 @@ impl LatestBlockhashStorage {
         self.slot_processed = self.slot_processed.max(self.slot_confirmed);
     }
 
-    fn push_block(&mut self, slot: Slot, parent: Slot, height: Slot, hash: Hash) {
+    fn push_block(
+        &mut self,
+        slot: Slot,
+        parent: Slot,
+        height: Slot,
+        hash: Hash,
+        time: UnixTimestamp,
+    ) {
         // in case if slot status was received before block
         let mut commitment = CommitmentLevel::Processed;
         if self.confirmed.contains(&slot) {
@@ -1187,8 +2514,10 @@ impl LatestBlockhashStorage {
                 parent,
                 height,
                 hash,
+                time,
             },
         );
+        self.hashes.insert(hash, slot);
 
         // keep slots under the limit (based only on finalized count)
         let slots_to_remove = self
@@ -1200,6 +2529,7 @@ impl LatestBlockhashStorage {
             .unwrap_or_default();
         for _ in 0..slots_to_remove {
             while let Some((_slot, value)) = self.slots.pop_first() {
+                self.hashes.remove(&value.hash);
                 if value.commitment == CommitmentLevel::Finalized {
                     break;
                 }
@@ -1260,82 +2590,45 @@ impl LatestBlockhashStorage {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 struct LatestBlockhashSlot {
     commitment: CommitmentLevel,
     parent: Slot,
     height: Slot,
     hash: Hash,
+    time: UnixTimestamp,
 }
 
 #[derive(Debug, Clone)]
 struct StreamsSlotInfo {
-    leader: Option<Pubkey>,
-    slot: Slot,
-    parent_slot: Slot,
     commitment: CommitmentLevel,
-    hash: Hash,
-    time: UnixTimestamp,
-    height: Slot,
-    transactions: Arc<Vec<GeyserTransaction>>,
-    total_transactions_vote: usize,
-    fees: Arc<RecentPrioritizationFeesSlot>, // only for solana `getRecentPrioritizationFees`
-    total_fee: u64,
-    total_units_consumed: u64,
+    summary: Arc<SlotSummary>,
+    fees: Arc<RecentPrioritizationFeesSlot>,
 }
 
 impl StreamsSlotInfo {
-    fn new(
-        leader: Option<Pubkey>,
-        slot: Slot,
-        parent_slot: Slot,
-        hash: Hash,
-        time: UnixTimestamp,
-        height: Slot,
-        transactions: Arc<Vec<GeyserTransaction>>,
-    ) -> Self {
-        let total_transactions_vote = transactions.iter().filter(|tx| tx.vote).count();
-
-        let fees = Arc::new(RecentPrioritizationFeesSlot::create(&transactions));
-        let total_fee = transactions.iter().map(|tx| tx.fee).sum();
-
-        let total_units_consumed = transactions
-            .iter()
-            .filter(|tx| !tx.vote)
-            .map(|tx| tx.units_consumed.unwrap_or_default())
-            .sum::<u64>();
-
+    fn new(summary: Arc<SlotSummary>) -> Self {
         Self {
-            leader,
-            slot,
-            parent_slot,
             commitment: CommitmentLevel::Processed,
-            hash,
-            time,
-            height,
-            transactions,
-            total_transactions_vote,
-            fees,
-            total_fee,
-            total_units_consumed,
+            fees: Arc::new(RecentPrioritizationFeesSlot::create(&summary)),
+            summary,
         }
     }
 
     fn get_filtered(&self, filter: &SlotSubscribeFilter) -> SlotsSubscribeOutput {
         let total_transactions_filtered = self
+            .summary
             .transactions
             .iter()
             .filter(|tx| {
-                !tx.vote
-                    && tx.unit_limit > 0
-                    && filter
-                        .read_write
-                        .iter()
-                        .all(|pubkey| tx.accounts.writable.contains(pubkey))
+                filter
+                    .read_write
+                    .iter()
+                    .all(|pubkey| tx.writable.binary_search(pubkey).is_ok())
                     && filter
                         .read_only
                         .iter()
-                        .all(|pubkey| tx.accounts.readable.contains(pubkey))
+                        .all(|pubkey| tx.readable.binary_search(pubkey).is_ok())
             })
             .count();
 
@@ -1364,21 +2657,22 @@ impl StreamsSlotInfo {
             })
             .collect();
 
+        let summary = &self.summary;
         SlotsSubscribeOutput::Slot {
-            leader: self.leader.map(|pk| pk.to_string()).unwrap_or_default(),
-            slot: self.slot,
-            parent_slot: self.parent_slot,
+            leader: summary.leader.map(|pk| pk.to_string()).unwrap_or_default(),
+            slot: summary.slot,
+            parent_slot: summary.parent_slot,
             commitment: self.commitment,
-            hash: self.hash.to_string(),
-            time: self.time,
-            height: self.height,
+            hash: summary.hash.to_string(),
+            time: summary.time,
+            height: summary.height,
             total_transactions_filtered,
-            total_transactions_vote: self.total_transactions_vote,
-            total_transactions: self.transactions.len(),
+            total_transactions_vote: summary.total_transactions_vote as usize,
+            total_transactions: summary.total_transactions as usize,
             fee_average,
             fee_levels,
-            total_fee: self.total_fee,
-            total_units_consumed: self.total_units_consumed,
+            total_fee: summary.total_fee,
+            total_units_consumed: summary.total_units_consumed,
         }
     }
 }
@@ -1394,66 +2688,30 @@ struct RecentPrioritizationFeesSlot {
 }
 
 impl RecentPrioritizationFeesSlot {
-    fn create(transactions: &[GeyserTransaction]) -> Self {
-        let mut transaction_fees = Vec::with_capacity(transactions.len());
-        let mut transaction_fees_nz = Vec::with_capacity(transactions.len());
-        let mut writable_account_fees =
-            HashMap::<Pubkey, Vec<u64>>::with_capacity(transactions.len());
-        let mut writable_account_fees_nz =
-            HashMap::<Pubkey, Vec<u64>>::with_capacity(transactions.len());
-        let mut readable_account_fees =
-            HashMap::<Pubkey, Vec<u64>>::with_capacity(transactions.len());
-        let mut readable_account_fees_nz =
-            HashMap::<Pubkey, Vec<u64>>::with_capacity(transactions.len());
-
-        for transaction in transactions
-            .iter()
-            .filter(|tx| !tx.vote && tx.unit_limit > 0)
-        {
-            transaction_fees.push(transaction.unit_price);
-            for account in transaction.accounts.writable.iter().copied() {
-                writable_account_fees
-                    .entry(account)
-                    .or_default()
-                    .push(transaction.unit_price);
-            }
-            for account in transaction.accounts.readable.iter().copied() {
-                readable_account_fees
-                    .entry(account)
-                    .or_default()
-                    .push(transaction.unit_price);
-            }
-
-            if transaction.unit_price > 0 {
-                transaction_fees_nz.push(transaction.unit_price);
-                for account in transaction.accounts.writable.iter().copied() {
-                    writable_account_fees_nz
-                        .entry(account)
-                        .or_default()
-                        .push(transaction.unit_price);
-                }
-                for account in transaction.accounts.readable.iter().copied() {
-                    readable_account_fees_nz
-                        .entry(account)
-                        .or_default()
-                        .push(transaction.unit_price);
-                }
-            }
-        }
-
-        let conv = |map: HashMap<Pubkey, Vec<u64>>| {
-            map.into_iter()
-                .map(|(account, fees)| (account, CollectedFees::new(fees)))
+    fn create(summary: &SlotSummary) -> Self {
+        // fees are sorted, so non-zero fees are at the end
+        let non_zero = |fees: &[u64]| fees[fees.partition_point(|fee| *fee == 0)..].to_vec();
+        let conv = |map: &HashMap<Pubkey, Vec<u64>>| {
+            map.iter()
+                .map(|(account, fees)| (*account, CollectedFees::new(fees.clone())))
+                .collect()
+        };
+        let conv_nz = |map: &HashMap<Pubkey, Vec<u64>>| {
+            map.iter()
+                .filter_map(|(account, fees)| {
+                    let fees = non_zero(fees);
+                    (!fees.is_empty()).then(|| (*account, CollectedFees::new(fees)))
+                })
                 .collect()
         };
 
         Self {
-            transaction_fees: CollectedFees::new(transaction_fees),
-            transaction_fees_nz: CollectedFees::new(transaction_fees_nz),
-            writable_account_fees: conv(writable_account_fees),
-            writable_account_fees_nz: conv(writable_account_fees_nz),
-            readable_account_fees: conv(readable_account_fees),
-            readable_account_fees_nz: conv(readable_account_fees_nz),
+            transaction_fees: CollectedFees::new(summary.fees.clone()),
+            transaction_fees_nz: CollectedFees::new(non_zero(&summary.fees)),
+            writable_account_fees: conv(&summary.writable_fees),
+            writable_account_fees_nz: conv_nz(&summary.writable_fees),
+            readable_account_fees: conv(&summary.readable_fees),
+            readable_account_fees_nz: conv_nz(&summary.readable_fees),
         }
     }
 
@@ -1558,15 +2816,15 @@ struct ReqParamsSlotsSubscribe {
 
 #[derive(Debug, Default, Deserialize)]
 #[serde(default, rename_all = "camelCase")]
-struct ReqParamsSlotsSubscribeConfig {
-    read_write: Vec<String>,
-    read_only: Vec<String>,
-    levels: Vec<u16>,
-    skip_zeros: bool,
+pub struct ReqParamsSlotsSubscribeConfig {
+    pub read_write: Vec<String>,
+    pub read_only: Vec<String>,
+    pub levels: Vec<u16>,
+    pub skip_zeros: bool,
 }
 
 #[derive(Debug)]
-struct SlotSubscribeFilter {
+pub struct SlotSubscribeFilter {
     read_write: Vec<Pubkey>,
     read_only: Vec<Pubkey>,
     levels: Vec<u16>,
@@ -0,0 +1,222 @@
+use {
+    crate::config::ConfigTls,
+    anyhow::Context,
+    arc_swap::ArcSwap,
+    std::{
+        net::SocketAddr,
+        path::Path,
+        pin::Pin,
+        sync::{Arc, Mutex, Weak},
+        time::{Duration, SystemTime},
+    },
+    tokio::{
+        fs,
+        io::{AsyncRead, AsyncWrite},
+        net::{TcpListener, TcpStream},
+        sync::mpsc,
+        time::{interval, timeout, MissedTickBehavior},
+    },
+    tokio_rustls::{
+        rustls::{crypto::ring, server::WebPkiClientVerifier, RootCertStore, ServerConfig},
+        server::TlsStream,
+    },
+    tracing::{debug, error, info},
+};
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Plain TCP or TLS stream
+pub trait AsyncStream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> AsyncStream for T {}
+
+pub type BoxedStream = Pin<Box<dyn AsyncStream>>;
+
+/// TLS acceptor, certificates reloaded on files modification or with `reload`
+#[derive(Debug)]
+pub struct TlsAcceptor {
+    config: ConfigTls,
+    server_config: ArcSwap<ServerConfig>,
+    modified: Mutex<Vec<Option<SystemTime>>>,
+}
+
+impl TlsAcceptor {
+    pub async fn new(config: ConfigTls) -> anyhow::Result<Arc<Self>> {
+        let modified = Self::get_modified(&config).await;
+        let server_config = Self::load(&config).await?;
+        let acceptor = Arc::new(Self {
+            config,
+            server_config: ArcSwap::from_pointee(server_config),
+            modified: Mutex::new(modified),
+        });
+
+        tokio::spawn(Self::run_watcher(
+            Arc::downgrade(&acceptor),
+            acceptor.config.reload_interval,
+        ));
+
+        Ok(acceptor)
+    }
+
+    pub async fn new_maybe(config: Option<ConfigTls>) -> anyhow::Result<Option<Arc<Self>>> {
+        match config {
+            Some(config) => Self::new(config).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn run_watcher(acceptor: Weak<Self>, reload_interval: Duration) {
+        let mut interval = interval(reload_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            let Some(acceptor) = acceptor.upgrade() else {
+                break;
+            };
+
+            let modified = Self::get_modified(&acceptor.config).await;
+            if *acceptor.modified.lock().expect("unpoisoned mutex") == modified {
+                continue;
+            }
+
+            info!(cert = ?acceptor.config.cert, "TLS files modified, reload");
+            if let Err(error) = acceptor.reload().await {
+                error!(?error, "failed to reload TLS certificates");
+            }
+        }
+    }
+
+    async fn get_modified(config: &ConfigTls) -> Vec<Option<SystemTime>> {
+        let mut modified = vec![];
+        for path in [
+            Some(&config.cert),
+            Some(&config.key),
+            config.client_ca.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            let ts = fs::metadata(path)
+                .await
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            modified.push(ts);
+        }
+        modified
+    }
+
+    async fn read_pem(path: &Path) -> anyhow::Result<Vec<u8>> {
+        fs::read(path)
+            .await
+            .with_context(|| format!("failed to read file: {path:?}"))
+    }
+
+    async fn load(config: &ConfigTls) -> anyhow::Result<ServerConfig> {
+        let cert = Self::read_pem(&config.cert).await?;
+        let certs = rustls_pemfile::certs(&mut cert.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .context("failed to parse certificates")?;
+        anyhow::ensure!(!certs.is_empty(), "no certificates in {:?}", config.cert);
+
+        let key = Self::read_pem(&config.key).await?;
+        let key = rustls_pemfile::private_key(&mut key.as_slice())
+            .context("failed to parse private key")?
+            .ok_or_else(|| anyhow::anyhow!("no private key in {:?}", config.key))?;
+
+        let provider = Arc::new(ring::default_provider());
+        let builder = ServerConfig::builder_with_provider(Arc::clone(&provider))
+            .with_safe_default_protocol_versions()
+            .context("failed to create TLS config")?;
+        let builder = match &config.client_ca {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in rustls_pemfile::certs(&mut Self::read_pem(path).await?.as_slice()) {
+                    roots
+                        .add(cert.context("failed to parse client CA certificate")?)
+                        .context("failed to add client CA certificate")?;
+                }
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                        .build()
+                        .context("failed to create client certificate verifier")?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let mut server_config = builder
+            .with_single_cert(certs, key)
+            .context("invalid certificate or private key")?;
+        server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(server_config)
+    }
+
+    pub async fn reload(&self) -> anyhow::Result<()> {
+        let modified = Self::get_modified(&self.config).await;
+        // save modification time first, so broken files would not be reloaded on every tick
+        *self.modified.lock().expect("unpoisoned mutex") = modified;
+
+        let server_config = Self::load(&self.config).await?;
+        self.server_config.store(Arc::new(server_config));
+        info!(cert = ?self.config.cert, "TLS certificates loaded");
+        Ok(())
+    }
+
+    pub async fn accept(&self, stream: TcpStream) -> anyhow::Result<TlsStream<TcpStream>> {
+        let acceptor = tokio_rustls::TlsAcceptor::from(self.server_config.load_full());
+        timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream))
+            .await
+            .context("TLS handshake timeout")?
+            .context("TLS handshake failed")
+    }
+}
+
+/// TCP listener with optional TLS, handshake is made in spawned task
+#[derive(Debug)]
+pub struct Listener {
+    listener: TcpListener,
+    tls: Option<Arc<TlsAcceptor>>,
+    accepted_tx: mpsc::UnboundedSender<(BoxedStream, SocketAddr)>,
+    accepted_rx: mpsc::UnboundedReceiver<(BoxedStream, SocketAddr)>,
+}
+
+impl Listener {
+    pub async fn bind(addr: SocketAddr, tls: Option<Arc<TlsAcceptor>>) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let (accepted_tx, accepted_rx) = mpsc::unbounded_channel();
+        Ok(Self {
+            listener,
+            tls,
+            accepted_tx,
+            accepted_rx,
+        })
+    }
+
+    // cancel safe
+    pub async fn accept(&mut self) -> anyhow::Result<(BoxedStream, SocketAddr)> {
+        loop {
+            tokio::select! {
+                maybe_incoming = self.listener.accept() => {
+                    let (stream, addr) = maybe_incoming?;
+                    let Some(tls) = &self.tls else {
+                        return Ok((Box::pin(stream), addr));
+                    };
+
+                    let tls = Arc::clone(tls);
+                    let accepted_tx = self.accepted_tx.clone();
+                    tokio::spawn(async move {
+                        match tls.accept(stream).await {
+                            Ok(stream) => {
+                                let _ = accepted_tx.send((Box::pin(stream), addr));
+                            }
+                            Err(error) => debug!(%addr, ?error, "failed to accept connection"),
+                        }
+                    });
+                }
+                Some(incoming) = self.accepted_rx.recv() => return Ok(incoming),
+            }
+        }
+    }
+}